@@ -0,0 +1,54 @@
+use std::{fmt::Debug, hash::Hash};
+
+use serde::{Deserialize, Serialize};
+
+/// What can be done with the file of a [`Document`](crate::Document), checked when compiling: [`ReadOnly`](ReadOnly) or [`ReadWrite`](ReadWrite).
+///
+/// This trait cannot be implemented outside of this library.
+pub trait Access:
+    sealed::Sealed + Debug + Clone + Copy + PartialEq + Eq + Hash + Default + Send + Sync + 'static
+{
+}
+
+mod sealed {
+    pub trait Sealed {
+        const WRITABLE: bool;
+    }
+    impl Sealed for super::ReadOnly {
+        const WRITABLE: bool = false;
+    }
+    impl Sealed for super::ReadWrite {
+        const WRITABLE: bool = true;
+    }
+}
+
+/// Whether a Document with the [`Access`](Access) `A` can write its file, for the methods which check the [`Mode`](crate::Mode) when running.
+pub(crate) fn is_writable<A: Access>() -> bool {
+    A::WRITABLE
+}
+
+/// Marks a [`Document`](crate::Document) which can only be read: `Document<ReadOnly>`.
+///
+/// Only the methods which read the file, its metadata or its location are available, so calling e.g. [`replace_with`](crate::Document::replace_with)
+/// on it fails to compile. [`open`](crate::Document::open) is available too, but returns an error for the [`Mode`](crate::Mode)s which write.
+/// Get one with [`read_only`](crate::Document::read_only), [`.read_only()`](crate::document::ResultDocumentExt::read_only)
+/// or [`DocumentMap::read_only`](crate::DocumentMap::read_only).
+///
+/// ```compile_fail
+/// use documents::prelude::*;
+///
+/// fn clear(settings: &mut Document<ReadOnly>) {
+///     settings.replace_with(b"").unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct ReadOnly;
+
+/// Marks a [`Document`](crate::Document) which can be read and written: `Document<ReadWrite>`, or just `Document`.
+///
+/// Every Document is ReadWrite unless it is turned into a [`ReadOnly`](ReadOnly) one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct ReadWrite;
+
+impl Access for ReadOnly {}
+impl Access for ReadWrite {}
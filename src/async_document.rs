@@ -0,0 +1,147 @@
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{self, BufReader, Lines},
+    panic,
+    path::PathBuf,
+};
+
+use crate::{
+    backend::BackendRef, Access, Create, Document, DocumentError, FileHandle, FileSystemEntity,
+    FolderPath,
+};
+
+/// How many lines [`AsyncLines`](AsyncLines) reads at a time.
+const LINES_PER_READ: usize = 64;
+
+/// Run `operation` on the blocking threads of the tokio runtime, so that it does not block the executor.
+pub(crate) async fn blocking<T: Send + 'static>(
+    operation: impl FnOnce() -> T + Send + 'static,
+) -> T {
+    match tokio::task::spawn_blocking(operation).await {
+        Ok(value) => value,
+        Err(error) => match error.try_into_panic() {
+            Ok(payload) => panic::resume_unwind(payload),
+            Err(error) => panic!("{error}"),
+        },
+    }
+}
+
+/// Async versions of the methods of [`Document`](Document), which do their I/O on the blocking threads of the tokio runtime.
+///
+/// Requires the `async` feature, and must be called from within a tokio runtime.
+impl Document {
+    /// Same as [`Document::at`](Document::at), but carries out the `create` policy without blocking the executor.
+    ///
+    /// The folder is resolved with the current [`Roots`](crate::Roots) and [`Backend`](crate::Backend) of the calling thread.
+    pub async fn at_async(
+        location: impl Into<FolderPath>,
+        filename: impl Display,
+        create: Create,
+    ) -> Result<Self, DocumentError> {
+        let pathbuf = location.into().to_pathbuf_result(filename.to_string())?;
+        let alias = pathbuf.name();
+        let backend = BackendRef::default();
+        blocking(move || Document::with_backend(backend, pathbuf, alias, create)).await
+    }
+
+    /// Same as [`Document::at_path`](Document::at_path), but carries out the `create` policy without blocking the executor.
+    pub async fn at_path_async(
+        path: impl Display,
+        alias: impl Display,
+        create: Create,
+    ) -> Result<Self, DocumentError> {
+        let pathbuf = PathBuf::from(path.to_string());
+        let alias = alias.to_string();
+        let backend = BackendRef::default();
+        blocking(move || Document::with_backend(backend, pathbuf, alias, create)).await
+    }
+
+    /// Same as [`append`](Document::append), without blocking the executor.
+    pub async fn append_async(&mut self, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        let (mut document, content) = (self.clone(), content.to_vec());
+        blocking(move || document.append(&content).map(|_| ())).await?;
+        Ok(self)
+    }
+
+    /// Same as [`replace_with`](Document::replace_with), without blocking the executor.
+    pub async fn replace_with_async(&mut self, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        let (mut document, content) = (self.clone(), content.to_vec());
+        blocking(move || document.replace_with(&content).map(|_| ())).await?;
+        Ok(self)
+    }
+
+    /// Same as [`replace_atomically`](Document::replace_atomically), without blocking the executor.
+    pub async fn replace_atomically_async(
+        &mut self,
+        content: &[u8],
+    ) -> Result<&mut Self, DocumentError> {
+        let (mut document, content) = (self.clone(), content.to_vec());
+        blocking(move || document.replace_atomically(&content).map(|_| ())).await?;
+        Ok(self)
+    }
+}
+
+/// Async versions of the methods of [`Document`](Document) which read the file, which read-only Documents have too.
+impl<A: Access> Document<A> {
+    /// Same as [`content`](Document::content), without blocking the executor.
+    pub async fn content_async(&self) -> Result<String, DocumentError> {
+        let document = self.clone();
+        blocking(move || document.content()).await
+    }
+
+    /// Same as [`bytes`](Document::bytes), without blocking the executor.
+    pub async fn bytes_async(&self) -> Result<Vec<u8>, DocumentError> {
+        let document = self.clone();
+        blocking(move || document.bytes()).await
+    }
+
+    /// Same as [`lines`](Document::lines), but the lines are read without blocking the executor. See [`AsyncLines`](AsyncLines).
+    ///
+    /// ```ignore
+    /// let mut lines = document.lines_async().await?;
+    /// while let Some(line) = lines.next_line().await? {
+    ///     println!("{line}");
+    /// }
+    /// ```
+    pub async fn lines_async(&self) -> Result<AsyncLines, DocumentError> {
+        let document = self.clone();
+        let lines = blocking(move || document.lines()).await?;
+        Ok(AsyncLines {
+            lines: Some(lines),
+            read: VecDeque::new(),
+        })
+    }
+}
+
+/// The lines of a file, read without blocking the executor. Provided by [`Document::lines_async`](Document::lines_async).
+///
+/// Lines are read a few at a time on the blocking threads of the tokio runtime. Requires the `async` feature.
+#[derive(Debug)]
+pub struct AsyncLines {
+    /// None once the end of the file or an error has been reached.
+    lines: Option<Lines<BufReader<Box<dyn FileHandle>>>>,
+    read: VecDeque<io::Result<String>>,
+}
+
+impl AsyncLines {
+    /// The next line of the file, without the line ending. Returns None at the end of the file.
+    ///
+    /// Returns an error if the line could not be read.
+    pub async fn next_line(&mut self) -> io::Result<Option<String>> {
+        if self.read.is_empty() {
+            if let Some(mut lines) = self.lines.take() {
+                let (lines, read) = blocking(move || {
+                    let read: VecDeque<_> = lines.by_ref().take(LINES_PER_READ).collect();
+                    let finished =
+                        read.len() < LINES_PER_READ || read.back().is_some_and(Result::is_err);
+                    ((!finished).then_some(lines), read)
+                })
+                .await;
+                self.lines = lines;
+                self.read = read;
+            }
+        }
+        self.read.pop_front().transpose()
+    }
+}
@@ -0,0 +1,930 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::{self, File, FileTimes, OpenOptions, TryLockError},
+    hash::{Hash, Hasher},
+    io::{self, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, LazyLock, Mutex, MutexGuard, RwLock,
+    },
+    time::SystemTime,
+};
+
+use crate::{EntryKind, LockKind, Metadata, Mode};
+
+/// A file opened by a [`Backend`](Backend), which can be read, written and seeked like a [`File`](std::fs::File).
+pub trait FileHandle: Read + Write + Seek + Debug + Send {
+    /// Wait for the content written to this file to reach the disk.
+    fn sync_data(&mut self) -> io::Result<()>;
+
+    /// Wait for the content and metadata of this file to reach the disk.
+    fn sync_all(&mut self) -> io::Result<()>;
+
+    /// Wait until an advisory lock of `kind` can be taken on this file, and take it. A lock already held through this handle is replaced.
+    /// Fails with `Unsupported` by default.
+    fn lock(&mut self, kind: LockKind) -> io::Result<()> {
+        let _ = kind;
+        Err(locking_unsupported())
+    }
+
+    /// Take an advisory lock of `kind` on this file if it can be taken right away. Returns false if another handle holds a conflicting lock.
+    /// Fails with `Unsupported` by default.
+    fn try_lock(&mut self, kind: LockKind) -> io::Result<bool> {
+        let _ = kind;
+        Err(locking_unsupported())
+    }
+
+    /// Release the advisory lock held through this handle, if any. Closing the handle releases it too.
+    fn unlock(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn locking_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "this backend does not support locking files",
+    )
+}
+
+impl FileHandle for File {
+    fn sync_data(&mut self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+    fn sync_all(&mut self) -> io::Result<()> {
+        File::sync_all(self)
+    }
+    fn lock(&mut self, kind: LockKind) -> io::Result<()> {
+        match kind {
+            LockKind::Shared => File::lock_shared(self),
+            LockKind::Exclusive => File::lock(self),
+        }
+    }
+    fn try_lock(&mut self, kind: LockKind) -> io::Result<bool> {
+        let result = match kind {
+            LockKind::Shared => File::try_lock_shared(self),
+            LockKind::Exclusive => File::try_lock(self),
+        };
+        match result {
+            Ok(()) => Ok(true),
+            Err(TryLockError::WouldBlock) => Ok(false),
+            Err(TryLockError::Error(error)) => Err(error),
+        }
+    }
+    fn unlock(&mut self) -> io::Result<()> {
+        File::unlock(self)
+    }
+}
+
+/// Where the files and folders of [`Document`](crate::Document)s are stored. Every Document operation, [`Create`](crate::Create) policy,
+/// [`Walk`](crate::Walk) and folder listing goes through the current Backend.
+///
+/// *LocalDisk*: the disk of this device, used by default.
+///
+/// *InMemory*: files and folders which only exist in memory, so that tests do not do any disk I/O.
+///
+/// *Faulty*: another Backend whose operations fail as scripted, to test error handling. See [`Faulty`](crate::Faulty).
+///
+/// The current Backend is, from the most important to the least important: the Backend entered on this thread with [`enter`](Backend::enter)
+/// or [`WithOptions::backend`](crate::WithOptions::backend), the Backend set for the whole program with [`set_backend`](set_backend), and [`LocalDisk`](LocalDisk).
+/// A Document keeps using the Backend which was current when it was created.
+///
+/// Operations return [`io::Error`](std::io::Error)s with the same [`ErrorKind`](std::io::ErrorKind)s as the standard library, e.g. `NotFound` or `AlreadyExists`,
+/// which Documents wrap in [`DocumentError`](crate::DocumentError)s.
+///
+/// ```ignore
+/// #[test]
+/// fn saves_notes() {
+///     let memory = InMemory::new();
+///     let _backend = memory.clone().enter();
+///     let mut notes = Document::at(User(Documents([])), "notes.txt", Create::OnlyIfNotExists).unwrap();
+///     notes.append(b"Never on disk").unwrap();
+///     assert!(memory.exists(Path::new(&notes.path())));
+/// }
+/// ```
+pub trait Backend: Any + Debug + Send + Sync {
+    /// Open the existing file at `path` with the permissions of `mode`.
+    fn open(&self, path: &Path, mode: Mode) -> io::Result<Box<dyn FileHandle>>;
+
+    /// Create a new empty file at `path` and open it for writing. Fails with `AlreadyExists` if there already is something at `path`.
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn FileHandle>>;
+
+    /// Create the folder at `path` and any missing folders above it.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Whether there is a file or folder at `path`.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// The metadata of the file or folder at `path`.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Rename the file or folder at `from` to `to`, replacing the file at `to` if there is one.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Remove the file at `path`.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Remove the empty folder at `path`.
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// The paths of the files and folders directly in the folder at `path`, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Set the last accessed and last modified times of the file at `path`. Times which are None are left as they are.
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()>;
+
+    /// Make the file at `path` read-only, or writable again.
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()>;
+
+    /// Set the unix permission bits of the file at `path`.
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()>;
+
+    /// Copy the content of the file at `from` to the file at `to`, replacing it if it exists. Returns the number of bytes copied.
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        let mut source = self.open(from, Mode::Read)?;
+        if self.exists(to) {
+            self.remove_file(to)?;
+        }
+        let mut destination = self.create_new(to)?;
+        let bytes_copied = io::copy(&mut source, &mut destination)?;
+        destination.flush()?;
+        Ok(bytes_copied)
+    }
+
+    /// Wait for the entries of the folder at `path`, e.g. a file renamed into it, to reach the disk. Does nothing by default.
+    fn sync_folder(&self, path: &Path) -> io::Result<()> {
+        let _ = path;
+        Ok(())
+    }
+
+    /// Open the file at `path` as a [`File`](std::fs::File), for [`Document::file`](crate::Document::file).
+    /// Fails with `Unsupported` by default, because only files on the disk can be Files.
+    fn std_file(&self, path: &Path, mode: Mode) -> io::Result<File> {
+        let _ = (path, mode);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this backend does not store files on the disk",
+        ))
+    }
+
+    /// Use this Backend on this thread until the returned guard is dropped.
+    fn enter(self) -> BackendGuard
+    where
+        Self: Sized + 'static,
+    {
+        enter_backend(Arc::new(self))
+    }
+}
+
+static LOCAL_DISK: LazyLock<Arc<dyn Backend>> = LazyLock::new(|| Arc::new(LocalDisk));
+
+static GLOBAL_BACKEND: RwLock<Option<Arc<dyn Backend>>> = RwLock::new(None);
+
+thread_local! {
+    /// The entered Backends with the id of their guard, the current one last.
+    static ENTERED_BACKENDS: RefCell<Vec<(usize, Arc<dyn Backend>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Use `backend` for the whole program, on every thread which has not [`entered`](Backend::enter) its own Backend.
+pub fn set_backend(backend: impl Backend + 'static) {
+    let mut global_backend = GLOBAL_BACKEND
+        .write()
+        .unwrap_or_else(|error| error.into_inner());
+    *global_backend = Some(Arc::new(backend));
+}
+
+/// Go back to [`LocalDisk`](LocalDisk) for the whole program, undoing [`set_backend`](set_backend).
+pub fn reset_backend() {
+    let mut global_backend = GLOBAL_BACKEND
+        .write()
+        .unwrap_or_else(|error| error.into_inner());
+    *global_backend = None;
+}
+
+pub(crate) fn enter_backend(backend: Arc<dyn Backend>) -> BackendGuard {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    ENTERED_BACKENDS.with(|entered| entered.borrow_mut().push((id, backend)));
+    BackendGuard {
+        id,
+        not_send: PhantomData,
+    }
+}
+
+/// The current [`Backend`](Backend).
+pub(crate) fn current_backend() -> Arc<dyn Backend> {
+    let entered = ENTERED_BACKENDS
+        .with(|entered| entered.borrow().last().map(|(_, backend)| backend.clone()));
+    if let Some(backend) = entered {
+        return backend;
+    }
+    let global = GLOBAL_BACKEND
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone();
+    global.unwrap_or_else(|| LOCAL_DISK.clone())
+}
+
+/// Keeps a [`Backend`](Backend) entered on this thread with [`enter`](Backend::enter). The Backend entered before is used again when this guard is dropped.
+///
+/// Guards can be dropped in any order: each one only takes back its own Backend, and the most recently entered Backend which is still kept is used.
+#[derive(Debug)]
+#[must_use = "the Backend is only used until this guard is dropped"]
+pub struct BackendGuard {
+    id: usize,
+    not_send: PhantomData<*const ()>,
+}
+
+impl Drop for BackendGuard {
+    fn drop(&mut self) {
+        // Guards may be dropped in any order, so remove this guard's own entry rather than the last one.
+        ENTERED_BACKENDS.with(|entered| entered.borrow_mut().retain(|(id, _)| *id != self.id));
+    }
+}
+
+/// Whether `backend` is [`LocalDisk`](LocalDisk), for operations which only make sense on the disk.
+#[cfg(all(unix, not(target_vendor = "apple")))]
+pub(crate) fn is_local_disk(backend: &dyn Backend) -> bool {
+    (backend as &dyn Any).is::<LocalDisk>()
+}
+
+/// The [`Backend`](Backend) a [`Document`](crate::Document) was created with. Two of them are equal if they are the same Backend.
+#[derive(Debug, Clone)]
+pub(crate) struct BackendRef(pub(crate) Arc<dyn Backend>);
+
+impl Default for BackendRef {
+    fn default() -> Self {
+        Self(current_backend())
+    }
+}
+
+impl PartialEq for BackendRef {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Hash for BackendRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
+
+/// The disk of this device, through the standard library. This is the default [`Backend`](Backend).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LocalDisk;
+
+impl Backend for LocalDisk {
+    fn open(&self, path: &Path, mode: Mode) -> io::Result<Box<dyn FileHandle>> {
+        Ok(Box::new(self.std_file(path, mode)?))
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn FileHandle>> {
+        let file = OpenOptions::new()
+            .read(false)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        Ok(Box::new(file))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        matches!(path.try_exists(), Ok(true))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let is_symlink = fs::symlink_metadata(path)?.is_symlink();
+        let metadata = fs::metadata(path)?;
+        let kind = if metadata.is_file() {
+            EntryKind::File
+        } else if metadata.is_dir() {
+            EntryKind::Folder
+        } else {
+            EntryKind::Other
+        };
+        #[cfg(unix)]
+        let (mode, device, inode) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Some(metadata.mode()),
+                Some(metadata.dev()),
+                Some(metadata.ino()),
+            )
+        };
+        #[cfg(not(unix))]
+        let (mode, device, inode) = (None, None, None);
+        Ok(Metadata {
+            kind,
+            size: metadata.len(),
+            created: metadata.created().ok(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            readonly: metadata.permissions().readonly(),
+            mode,
+            is_symlink,
+            device,
+            inode,
+        })
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        let mut times = FileTimes::new();
+        if let Some(accessed) = accessed {
+            times = times.set_accessed(accessed);
+        }
+        if let Some(modified) = modified {
+            times = times.set_modified(modified);
+        }
+        // Windows only allows changing the times of files opened for writing.
+        OpenOptions::new()
+            .read(cfg!(unix))
+            .write(!cfg!(unix))
+            .open(path)?
+            .set_times(times)
+    }
+
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()> {
+        let mut permissions = fs::metadata(path)?.permissions();
+        permissions.set_readonly(readonly);
+        fs::set_permissions(path, permissions)
+    }
+
+    #[cfg(unix)]
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+
+    #[cfg(not(unix))]
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        let _ = (path, mode);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unix permission bits are not supported on this system",
+        ))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        fs::copy(from, to)
+    }
+
+    fn sync_folder(&self, path: &Path) -> io::Result<()> {
+        #[cfg(unix)]
+        File::open(path)?.sync_all()?;
+        #[cfg(not(unix))]
+        let _ = path;
+        Ok(())
+    }
+
+    fn std_file(&self, path: &Path, mode: Mode) -> io::Result<File> {
+        OpenOptions::new()
+            .read(mode.readable())
+            .write(mode.writable())
+            .append(mode.appendable())
+            .truncate(mode.writable() && !mode.appendable())
+            .open(path)
+    }
+}
+
+/// A file or folder of an [`InMemory`](InMemory) backend.
+#[derive(Debug)]
+struct Node {
+    kind: EntryKind,
+    content: Vec<u8>,
+    created: SystemTime,
+    modified: SystemTime,
+    accessed: SystemTime,
+    mode: u32,
+    inode: u64,
+    /// Shared by every handle opened on this file, like the advisory locks of a file on the disk.
+    locks: Arc<NodeLocks>,
+}
+
+impl Node {
+    fn new(kind: EntryKind) -> Self {
+        static NEXT_INODE: AtomicU64 = AtomicU64::new(1);
+        let now = SystemTime::now();
+        Self {
+            kind,
+            content: Vec::new(),
+            created: now,
+            modified: now,
+            accessed: now,
+            mode: match kind {
+                EntryKind::Folder => 0o755,
+                _ => 0o644,
+            },
+            inode: NEXT_INODE.fetch_add(1, Ordering::Relaxed),
+            locks: Arc::default(),
+        }
+    }
+
+    fn readonly(&self) -> bool {
+        self.mode & 0o222 == 0
+    }
+}
+
+type SharedNode = Arc<Mutex<Node>>;
+
+/// The advisory locks held on a file of an [`InMemory`](InMemory) backend, and a way to wait for them to be released.
+#[derive(Debug, Default)]
+struct NodeLocks {
+    held: Mutex<HeldLocks>,
+    released: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct HeldLocks {
+    shared: usize,
+    exclusive: bool,
+}
+
+impl HeldLocks {
+    /// Take a lock of `kind` if it does not conflict with the locks already held.
+    fn take(&mut self, kind: LockKind) -> bool {
+        match kind {
+            LockKind::Shared if !self.exclusive => self.shared += 1,
+            LockKind::Exclusive if !self.exclusive && self.shared == 0 => self.exclusive = true,
+            _ => return false,
+        }
+        true
+    }
+
+    fn release(&mut self, kind: LockKind) {
+        match kind {
+            LockKind::Shared => self.shared -= 1,
+            LockKind::Exclusive => self.exclusive = false,
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+fn error(kind: io::ErrorKind, path: &Path) -> io::Error {
+    io::Error::new(kind, path.display().to_string())
+}
+
+/// Remove "." and trailing separators from `path`, so that the same path is always the same key.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Whether `path` has no parent folder, e.g. "/" or "C:\", and so always exists as a folder.
+fn is_top(path: &Path) -> bool {
+    path.parent().is_none_or(|parent| parent == Path::new(""))
+        && !matches!(path.components().next_back(), Some(Component::Normal(_)))
+}
+
+/// Files and folders which only exist in memory, for tests which should not touch the disk. Clones share the same files and folders.
+///
+/// Folders at the top, such as "/", always exist. Symbolic links are not supported.
+/// Unix permission bits are recorded, and files whose write bits are all unset cannot be opened for writing.
+#[derive(Clone, Default)]
+pub struct InMemory {
+    nodes: Arc<Mutex<BTreeMap<PathBuf, SharedNode>>>,
+}
+
+impl InMemory {
+    /// An empty InMemory backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn node(&self, path: &Path) -> io::Result<SharedNode> {
+        match lock(&self.nodes).get(&normalize(path)) {
+            Some(node) => Ok(node.clone()),
+            None => Err(error(io::ErrorKind::NotFound, path)),
+        }
+    }
+
+    /// Check that the parent folder of `path` exists in `nodes`.
+    fn check_parent(nodes: &BTreeMap<PathBuf, SharedNode>, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if !is_top(parent) && parent != Path::new("") => match nodes.get(parent) {
+                Some(node) if lock(node).kind == EntryKind::Folder => Ok(()),
+                Some(_) => Err(error(io::ErrorKind::NotADirectory, parent)),
+                None => Err(error(io::ErrorKind::NotFound, parent)),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether anything in `nodes` is inside the folder at `path`.
+    fn has_children(nodes: &BTreeMap<PathBuf, SharedNode>, path: &Path) -> bool {
+        nodes.keys().any(|key| key != path && key.starts_with(path))
+    }
+}
+
+impl Debug for InMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemory")
+            .field("entries", &lock(&self.nodes).len())
+            .finish()
+    }
+}
+
+impl Backend for InMemory {
+    fn open(&self, path: &Path, mode: Mode) -> io::Result<Box<dyn FileHandle>> {
+        let node = self.node(path)?;
+        {
+            let mut node = lock(&node);
+            if node.kind == EntryKind::Folder {
+                Err(error(io::ErrorKind::IsADirectory, path))?
+            }
+            if mode.writable() && node.readonly() {
+                Err(error(io::ErrorKind::PermissionDenied, path))?
+            }
+            if mode.readable() {
+                node.accessed = SystemTime::now();
+            }
+            if mode.writable() && !mode.appendable() {
+                node.content.clear();
+                node.modified = SystemTime::now();
+            }
+        }
+        Ok(Box::new(InMemoryFile {
+            node,
+            position: 0,
+            mode,
+            held: None,
+        }))
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn FileHandle>> {
+        let path = normalize(path);
+        let mut nodes = lock(&self.nodes);
+        if is_top(&path) || nodes.contains_key(&path) {
+            Err(error(io::ErrorKind::AlreadyExists, &path))?
+        }
+        Self::check_parent(&nodes, &path)?;
+        let node = Arc::new(Mutex::new(Node::new(EntryKind::File)));
+        nodes.insert(path, node.clone());
+        Ok(Box::new(InMemoryFile {
+            node,
+            position: 0,
+            mode: Mode::Replace,
+            held: None,
+        }))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = lock(&self.nodes);
+        let mut ancestors: Vec<&Path> = path
+            .ancestors()
+            .take_while(|folder| !is_top(folder) && *folder != Path::new(""))
+            .collect();
+        ancestors.reverse();
+        for folder in ancestors {
+            match nodes.get(folder) {
+                Some(node) if lock(node).kind == EntryKind::Folder => {}
+                Some(_) => Err(error(io::ErrorKind::AlreadyExists, folder))?,
+                None => {
+                    nodes.insert(
+                        folder.to_path_buf(),
+                        Arc::new(Mutex::new(Node::new(EntryKind::Folder))),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        is_top(&path) || lock(&self.nodes).contains_key(&path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if is_top(&normalize(path)) {
+            return Ok(Metadata {
+                kind: EntryKind::Folder,
+                size: 0,
+                created: None,
+                modified: None,
+                accessed: None,
+                readonly: false,
+                mode: Some(0o040755),
+                is_symlink: false,
+                device: Some(0),
+                inode: Some(0),
+            });
+        }
+        let node = self.node(path)?;
+        let node = lock(&node);
+        let file_type = match node.kind {
+            EntryKind::Folder => 0o040000,
+            _ => 0o100000,
+        };
+        Ok(Metadata {
+            kind: node.kind,
+            size: node.content.len() as u64,
+            created: Some(node.created),
+            modified: Some(node.modified),
+            accessed: Some(node.accessed),
+            readonly: node.readonly(),
+            mode: Some(file_type | node.mode),
+            is_symlink: false,
+            device: Some(0),
+            inode: Some(node.inode),
+        })
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (normalize(from), normalize(to));
+        let mut nodes = lock(&self.nodes);
+        let Some(node) = nodes.get(&from).cloned() else {
+            Err(error(io::ErrorKind::NotFound, &from))?
+        };
+        Self::check_parent(&nodes, &to)?;
+        if from == to {
+            return Ok(());
+        }
+        let from_kind = lock(&node).kind;
+        if let Some(existing) = nodes.get(&to) {
+            match (from_kind, lock(existing).kind) {
+                (EntryKind::Folder, EntryKind::Folder) if Self::has_children(&nodes, &to) => {
+                    Err(error(io::ErrorKind::DirectoryNotEmpty, &to))?
+                }
+                (EntryKind::Folder, EntryKind::Folder) => {}
+                (EntryKind::Folder, _) => Err(error(io::ErrorKind::NotADirectory, &to))?,
+                (_, EntryKind::Folder) => Err(error(io::ErrorKind::IsADirectory, &to))?,
+                _ => {}
+            }
+        }
+        if from_kind == EntryKind::Folder && to.starts_with(&from) {
+            Err(error(io::ErrorKind::InvalidInput, &to))?
+        }
+        let moved: Vec<PathBuf> = nodes
+            .keys()
+            .filter(|key| key.starts_with(&from))
+            .cloned()
+            .collect();
+        for key in moved {
+            if let Some(node) = nodes.remove(&key) {
+                let new_key = match key.strip_prefix(&from) {
+                    Ok(relative) if relative != Path::new("") => to.join(relative),
+                    _ => to.clone(),
+                };
+                nodes.insert(new_key, node);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = lock(&self.nodes);
+        match nodes.get(&path) {
+            Some(node) if lock(node).kind == EntryKind::Folder => {
+                Err(error(io::ErrorKind::IsADirectory, &path))
+            }
+            Some(_) => {
+                nodes.remove(&path);
+                Ok(())
+            }
+            None => Err(error(io::ErrorKind::NotFound, &path)),
+        }
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let path = normalize(path);
+        let mut nodes = lock(&self.nodes);
+        match nodes.get(&path) {
+            Some(node) if lock(node).kind != EntryKind::Folder => {
+                Err(error(io::ErrorKind::NotADirectory, &path))
+            }
+            Some(_) if Self::has_children(&nodes, &path) => {
+                Err(error(io::ErrorKind::DirectoryNotEmpty, &path))
+            }
+            Some(_) => {
+                nodes.remove(&path);
+                Ok(())
+            }
+            None => Err(error(io::ErrorKind::NotFound, &path)),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let path = normalize(path);
+        let nodes = lock(&self.nodes);
+        if !is_top(&path) {
+            match nodes.get(&path) {
+                Some(node) if lock(node).kind == EntryKind::Folder => {}
+                Some(_) => Err(error(io::ErrorKind::NotADirectory, &path))?,
+                None => Err(error(io::ErrorKind::NotFound, &path))?,
+            }
+        }
+        Ok(nodes
+            .keys()
+            .filter(|key| key.parent() == Some(path.as_path()))
+            .cloned()
+            .collect())
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        let node = self.node(path)?;
+        let mut node = lock(&node);
+        if let Some(accessed) = accessed {
+            node.accessed = accessed;
+        }
+        if let Some(modified) = modified {
+            node.modified = modified;
+        }
+        Ok(())
+    }
+
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()> {
+        let node = self.node(path)?;
+        let mut node = lock(&node);
+        if readonly {
+            node.mode &= !0o222;
+        } else {
+            node.mode |= 0o222;
+        }
+        Ok(())
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        let node = self.node(path)?;
+        lock(&node).mode = mode & 0o7777;
+        Ok(())
+    }
+}
+
+/// A file of an [`InMemory`](InMemory) backend opened with [`Backend::open`](Backend::open).
+struct InMemoryFile {
+    node: SharedNode,
+    position: u64,
+    mode: Mode,
+    /// The advisory lock held through this handle.
+    held: Option<LockKind>,
+}
+
+impl InMemoryFile {
+    /// Take a lock of `kind` on the file, waiting for conflicting locks to be released if `wait` is set.
+    fn take_lock(&mut self, kind: LockKind, wait: bool) -> io::Result<bool> {
+        self.unlock()?;
+        let locks = lock(&self.node).locks.clone();
+        let mut held = lock(&locks.held);
+        while !held.take(kind) {
+            if !wait {
+                return Ok(false);
+            }
+            held = locks
+                .released
+                .wait(held)
+                .unwrap_or_else(|error| error.into_inner());
+        }
+        self.held = Some(kind);
+        Ok(true)
+    }
+}
+
+impl Drop for InMemoryFile {
+    fn drop(&mut self) {
+        let _ = self.unlock();
+    }
+}
+
+impl Debug for InMemoryFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemoryFile")
+            .field("inode", &lock(&self.node).inode)
+            .field("position", &self.position)
+            .field("mode", &self.mode)
+            .field("held", &self.held)
+            .finish()
+    }
+}
+
+impl Read for InMemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.mode.readable() {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))?
+        }
+        let node = lock(&self.node);
+        let start = (self.position as usize).min(node.content.len());
+        let mut remaining = &node.content[start..];
+        let bytes_read = remaining.read(buf)?;
+        self.position += bytes_read as u64;
+        Ok(bytes_read)
+    }
+}
+
+impl Write for InMemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.mode.writable() {
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))?
+        }
+        let mut node = lock(&self.node);
+        if self.mode.appendable() {
+            self.position = node.content.len() as u64;
+        }
+        let start = self.position as usize;
+        let end = start + buf.len();
+        if node.content.len() < end {
+            node.content.resize(end, 0);
+        }
+        node.content[start..end].copy_from_slice(buf);
+        node.modified = SystemTime::now();
+        self.position = end as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for InMemoryFile {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match position {
+            SeekFrom::Start(offset) => {
+                self.position = offset;
+                return Ok(offset);
+            }
+            SeekFrom::End(offset) => (lock(&self.node).content.len() as u64, offset),
+            SeekFrom::Current(offset) => (self.position, offset),
+        };
+        match base.checked_add_signed(offset) {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+impl FileHandle for InMemoryFile {
+    fn sync_data(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+    fn sync_all(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+    fn lock(&mut self, kind: LockKind) -> io::Result<()> {
+        self.take_lock(kind, true).map(|_| ())
+    }
+    fn try_lock(&mut self, kind: LockKind) -> io::Result<bool> {
+        self.take_lock(kind, false)
+    }
+    fn unlock(&mut self) -> io::Result<()> {
+        if let Some(kind) = self.held.take() {
+            let locks = lock(&self.node).locks.clone();
+            lock(&locks.held).release(kind);
+            locks.released.notify_all();
+        }
+        Ok(())
+    }
+}
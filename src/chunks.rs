@@ -0,0 +1,54 @@
+use std::io::{BufReader, Read, Result};
+
+use crate::FileHandle;
+
+/// An iterator over the contents of a file in chunks of bytes, provided by [`Document::chunks`](crate::Document::chunks).
+///
+/// Every chunk has the requested chunk size (at least 1 byte), except for the last one which may be shorter.
+/// Each item is a [`Result`](std::io::Result), because reading any chunk may fail.
+///
+/// ```ignore
+/// for chunk in document.chunks(4096)? {
+///     hasher.update(&chunk?);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Chunks {
+    reader: BufReader<Box<dyn FileHandle>>,
+    chunk_size: usize,
+    finished: bool,
+}
+
+impl Chunks {
+    pub(crate) fn new(file: Box<dyn FileHandle>, chunk_size: usize) -> Self {
+        Self {
+            reader: BufReader::new(file),
+            chunk_size: chunk_size.max(1),
+            finished: false,
+        }
+    }
+}
+
+impl Iterator for Chunks {
+    type Item = Result<Vec<u8>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let mut chunk = Vec::with_capacity(self.chunk_size);
+        match (&mut self.reader)
+            .take(self.chunk_size as u64)
+            .read_to_end(&mut chunk)
+        {
+            Ok(0) => {
+                self.finished = true;
+                None
+            }
+            Ok(_) => Some(Ok(chunk)),
+            Err(error) => {
+                self.finished = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
@@ -1,15 +1,27 @@
 use core::fmt::Debug;
 use core::str;
 use extend::ext;
-use open;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
 use std::fmt::Display;
-use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::{BufRead, BufReader, BufWriter, Lines, Read, Write};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Lines, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
-use crate::{Create, DocumentError, FileSystemEntity, Folder, Mode};
+use crate::access::is_writable;
+use crate::backend::{Backend, BackendRef, FileHandle};
+use crate::glob::{glob_depth, glob_match};
+use crate::Format;
+use crate::{
+    Access, Chunks, Create, DocumentError, DocumentLock, Durability, EntryKind, FileSystemEntity,
+    FolderPath, LockKind, Metadata, Mode, OpenDocument, ReadOnly, ReadWrite, SortOrder,
+};
 
 /// A type that represents a file.
 ///
@@ -20,8 +32,18 @@ use crate::{Create, DocumentError, FileSystemEntity, Folder, Mode};
 ///
 /// Note: a Document is not the actual file. Creating an instance of this type will not create a new file.
 /// To specify whether to do so, use the `create` parameter of [`Document::at`](Document::at) or [`Document::at_path`](Document::at_path).
+///
+/// *A*: the [`Access`](Access) to the file through this Document, i.e. whether it can be written, checked when compiling.
+/// [`ReadWrite`](ReadWrite) by default, so `Document` is the same as `Document<ReadWrite>`.
+/// A `Document<ReadOnly>` made with [`read_only`](Document::read_only) only has the methods which read the file.
+///
+/// ```ignore
+/// let settings = Document::at(User(Documents([])), "settings.toml", Create::No).read_only()?;
+/// let content = settings.content()?;
+/// settings.replace_with(b"")?; // Does not compile
+/// ```
 #[derive(Clone, PartialEq, Hash, Debug, Serialize, Deserialize)]
-pub struct Document {
+pub struct Document<A = ReadWrite> {
     /// The alias of this Document in a [`DocumentMap`](DocumentMap), used to retrieve this Document from the DocumentMap.
     pub(crate) alias: String,
     /// The [`PathBuf`](std::path::PathBuf) of this Document. You can use `.display()` to convert it to something printable.
@@ -29,15 +51,70 @@ pub struct Document {
     /// The [`Create`](Create) policy of this Document, used to signal whether a new file should be created when creating an instance of Document.
     /// Can be `Create::No`, `Create::OnlyIfNotExists` or `Create::AutoRenameIfExists`
     pub(crate) create_policy: Create,
+    /// The [`Durability`](Durability) of write operations on this Document, i.e. whether to wait for written content to reach the disk.
+    #[serde(default)]
+    pub(crate) durability: Durability,
+    /// The folders and file created by the [`Create`](Create) policy when this Document was set up, in the order they were created.
+    /// [`try_with`](crate::try_with) removes them again if it fails.
+    #[serde(skip)]
+    pub(crate) created: Vec<PathBuf>,
+    /// The copy of the file which receives all reads and writes while writes are staged by [`try_with_options`](crate::try_with_options).
+    #[serde(skip)]
+    pub(crate) staging: Staging,
+    /// The [`Backend`](crate::Backend) which was current when this Document was created, which all of its operations go through.
+    #[serde(skip)]
+    pub(crate) backend: BackendRef,
+    #[serde(skip)]
+    access: PhantomData<A>,
 }
 
-impl Display for Document {
+impl<A: Access> Display for Document<A> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} at {}", self.name(), self.path())
     }
 }
 
-fn parse_filepath(pathbuf: PathBuf) -> (String, Option<i64>, Option<String>) {
+/// The staged copy of the file of a [`Document`](Document), if its writes are staged.
+///
+/// Shared by every clone of the Document and by the transaction which staged it, so that the transaction can send all of them
+/// back to the file itself when it is committed or rolled back, including Documents which were returned from the closure.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Staging(Arc<Mutex<Option<PathBuf>>>);
+
+impl Staging {
+    fn new(staged_pathbuf: PathBuf) -> Self {
+        Self(Arc::new(Mutex::new(Some(staged_pathbuf))))
+    }
+
+    pub(crate) fn pathbuf(&self) -> Option<PathBuf> {
+        self.0
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .clone()
+    }
+
+    /// Stop staging. Returns the staged copy, unless staging was already finished.
+    pub(crate) fn finish(&self) -> Option<PathBuf> {
+        self.0
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .take()
+    }
+}
+
+impl PartialEq for Staging {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.pathbuf() == other.pathbuf()
+    }
+}
+
+impl Hash for Staging {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pathbuf().hash(state);
+    }
+}
+
+pub(crate) fn parse_filepath(pathbuf: PathBuf) -> (String, Option<i64>, Option<String>) {
     let mut name = pathbuf.name();
     let extension = match ".".to_string()
         + pathbuf
@@ -55,8 +132,8 @@ fn parse_filepath(pathbuf: PathBuf) -> (String, Option<i64>, Option<String>) {
             None => name,
         };
     }
-    let open_bracket_index = (&name).rfind("(");
-    let close_bracket_index = (&name).rfind(")");
+    let open_bracket_index = name.rfind('(');
+    let close_bracket_index = name.rfind(')');
     let mut duplicate_number = None;
     if let Some(open_bracket_index) = open_bracket_index {
         if let Some(close_bracket_index) = close_bracket_index {
@@ -81,12 +158,165 @@ fn parse_filepath(pathbuf: PathBuf) -> (String, Option<i64>, Option<String>) {
     (name, duplicate_number, extension)
 }
 
+/// A path for a new temporary file next to `pathbuf`, which does not collide with other temporary files of this process.
+fn temp_pathbuf_for(pathbuf: &Path, purpose: &str) -> PathBuf {
+    static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    parent_folder_of(pathbuf).join(format!(
+        ".{}.{}-{}-{}.tmp",
+        pathbuf.to_path_buf().name(),
+        purpose,
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+fn parent_folder_of(pathbuf: &Path) -> &Path {
+    match pathbuf.parent() {
+        Some(parent_folder) if parent_folder != Path::new("") => parent_folder,
+        _ => Path::new("."),
+    }
+}
+
+/// Rename `from` over `to`, then flush the parent folder so that the rename itself survives a crash.
+///
+/// `from` must already be flushed to disk, e.g. with `sync_all` on the handle it was written through, or with [`sync_file`](sync_file).
+pub(crate) fn rename_durably(backend: &dyn Backend, from: &Path, to: &Path) -> io::Result<()> {
+    backend.rename(from, to)?;
+    backend.sync_folder(parent_folder_of(to))
+}
+
+/// Flush the file at `pathbuf` to disk, for files which were written through handles which are closed by now.
+/// Opened for appending if it cannot be read, which never changes its content.
+pub(crate) fn sync_file(backend: &dyn Backend, pathbuf: &Path) -> io::Result<()> {
+    let mut file = match backend.open(pathbuf, Mode::Read) {
+        Err(error) if error.kind() == io::ErrorKind::PermissionDenied => {
+            backend.open(pathbuf, Mode::Append)?
+        }
+        result => result?,
+    };
+    file.sync_all()
+}
+
+/// Write `content` to a temporary file next to `pathbuf`, flush it to disk and rename it over `pathbuf`,
+/// then flush the parent folder so that the rename itself survives a crash.
+fn write_atomically(
+    backend: &dyn Backend,
+    pathbuf: &Path,
+    content: &[u8],
+) -> Result<(), DocumentError> {
+    let temp_pathbuf = temp_pathbuf_for(pathbuf, "replace");
+    let mut temp_file = match backend.create_new(&temp_pathbuf) {
+        Ok(file) => file,
+        Err(error) => Err(DocumentError::CouldNotCreateFile(
+            temp_pathbuf.path(),
+            error,
+        ))?,
+    };
+    let result = (|| {
+        if let Ok(metadata) = backend.metadata(pathbuf) {
+            match metadata.mode {
+                Some(mode) => backend.set_mode(&temp_pathbuf, mode)?,
+                None => backend.set_readonly(&temp_pathbuf, metadata.readonly)?,
+            }
+        }
+        temp_file.write_all(content)?;
+        temp_file.flush()?;
+        temp_file.sync_all()?;
+        drop(temp_file);
+        rename_durably(backend, &temp_pathbuf, pathbuf)
+    })();
+    if let Err(error) = result {
+        let _ = backend.remove_file(&temp_pathbuf);
+        Err(DocumentError::CouldNotWriteFile(
+            pathbuf.to_path_buf().path(),
+            error,
+        ))?
+    }
+    Ok(())
+}
+
+/// Remove the files and folders in `created` in reverse order. Folders are only removed if they are empty.
+pub(crate) fn remove_created(backend: &dyn Backend, created: &[PathBuf]) {
+    for pathbuf in created.iter().rev() {
+        match backend.metadata(pathbuf) {
+            Ok(metadata) if metadata.kind == EntryKind::Folder => {
+                let _ = backend.remove_dir(pathbuf);
+            }
+            _ => {
+                let _ = backend.remove_file(pathbuf);
+            }
+        }
+    }
+}
+
+/// Create the parent folder of `pathbuf` and any missing folders above it, and record the ones which did not exist in `created`.
+fn create_parent_folder(
+    backend: &dyn Backend,
+    pathbuf: &Path,
+    created: &mut Vec<PathBuf>,
+) -> Result<(), DocumentError> {
+    let Some(parent_folder) = pathbuf.parent() else {
+        return Ok(());
+    };
+    let mut missing_folders: Vec<PathBuf> = parent_folder
+        .ancestors()
+        .take_while(|folder| *folder != Path::new("") && !backend.exists(folder))
+        .map(Path::to_path_buf)
+        .collect();
+    if let Err(error) = backend.create_dir_all(parent_folder) {
+        Err(DocumentError::CouldNotCreateParentFolder(
+            parent_folder.to_path_buf().display().to_string(),
+            error,
+        ))?
+    }
+    missing_folders.reverse();
+    created.append(&mut missing_folders);
+    Ok(())
+}
+
+/// Create a new empty file at `pathbuf` and record it in `created`.
+fn create_file(
+    backend: &dyn Backend,
+    pathbuf: &Path,
+    created: &mut Vec<PathBuf>,
+) -> Result<(), DocumentError> {
+    if let Err(error) = backend.create_new(pathbuf) {
+        Err(DocumentError::CouldNotCreateFile(
+            pathbuf.to_path_buf().path(),
+            error,
+        ))?
+    }
+    created.push(pathbuf.to_path_buf());
+    Ok(())
+}
+
 impl Document {
+    /// Carry out the `create` policy for `pathbuf`. Returns the path of the file (which may be renamed) and the folders and file created on the way.
+    ///
+    /// If the policy cannot be carried out, whatever was created on the way is removed again.
     fn setup(
+        backend: &dyn Backend,
+        pathbuf: PathBuf,
+        create: Create,
+        dry_run: bool,
+    ) -> Result<(PathBuf, Vec<PathBuf>), DocumentError> {
+        let mut created = Vec::new();
+        match Document::create_as_needed(backend, pathbuf, create, dry_run, &mut created) {
+            Ok(pathbuf) => Ok((pathbuf, created)),
+            Err(error) => {
+                remove_created(backend, &created);
+                Err(error)
+            }
+        }
+    }
+
+    fn create_as_needed(
+        backend: &dyn Backend,
         mut pathbuf: PathBuf,
         create: Create,
         dry_run: bool,
-    ) -> Result<PathBuf, Box<dyn Error>> {
+        created: &mut Vec<PathBuf>,
+    ) -> Result<PathBuf, DocumentError> {
         let (name, duplicate_number_option, extension_option) = parse_filepath(pathbuf.clone());
         let mut duplicate_number = 0;
         let mut extension = String::new();
@@ -98,36 +328,24 @@ impl Document {
         }
         match create {
             Create::OnlyIfNotExists => {
-                if let Some(parent_folder) = pathbuf.clone().parent() {
-                    if let Err(_) = create_dir_all(parent_folder) {
-                        Err(DocumentError::CouldNotCreateParentFolder(
-                            parent_folder.to_path_buf().display().to_string(),
-                        ))?
-                    }
+                if !dry_run {
+                    create_parent_folder(backend, &pathbuf, created)?;
                 }
-                if !pathbuf.exists() && !dry_run {
-                    OpenOptions::new()
-                        .read(false)
-                        .write(true)
-                        .create_new(true)
-                        .open(pathbuf.clone())?;
+                if !backend.exists(&pathbuf) && !dry_run {
+                    create_file(backend, &pathbuf, created)?;
                 }
             }
             Create::AutoRenameIfExists => {
-                if let Some(parent_folder) = pathbuf.clone().parent() {
-                    if let Err(_) = create_dir_all(parent_folder) {
-                        Err(DocumentError::CouldNotCreateParentFolder(
-                            parent_folder.to_path_buf().display().to_string(),
-                        ))?
-                    }
+                if !dry_run {
+                    create_parent_folder(backend, &pathbuf, created)?;
                 }
-                while pathbuf.exists() {
+                while backend.exists(&pathbuf) {
                     duplicate_number += 1;
                     let new_filename = name.clone()
                         + "("
                         + duplicate_number.to_string().as_str()
                         + ")"
-                        + if extension.clone().len() > 0 && extension.clone() != "." {
+                        + if !extension.is_empty() && extension.clone() != "." {
                             extension.as_str()
                         } else {
                             ""
@@ -139,16 +357,12 @@ impl Document {
                         .join(new_filename);
                 }
                 if !dry_run {
-                    OpenOptions::new()
-                        .read(false)
-                        .write(true)
-                        .create_new(true)
-                        .open(pathbuf.clone())?;
+                    create_file(backend, &pathbuf, created)?;
                 }
             }
             _ => {}
         }
-        if !pathbuf.exists() && !dry_run {
+        if !backend.exists(&pathbuf) && !dry_run {
             Err(DocumentError::FileNotFound(pathbuf.path()))?
         }
         Ok(pathbuf)
@@ -157,7 +371,7 @@ impl Document {
     /// Create an instance of [`Document`](Document) from a [`Folder`](Folder) location.
     ///
     /// *location*: the [`Folder`](Folder) which the file is in, e.g. `User(Pictures(["Screenshots"]))` or
-    /// `Project(Data([])).with_id("com", "github.kdwk", "Spidey")`.
+    /// `Project(Data([])).with_id("com", "github.kdwk", "Spidey")`, or a [`FolderPath`](FolderPath) built at runtime.
     ///
     /// *filename*: the name of the file with its file extension. Provide anything that can be converted to a string:
     /// a [`String`](std::string::String) (`String::new("example")`) or &str (`"example"`) --- anything goes.
@@ -169,19 +383,14 @@ impl Document {
     /// The `filename` will be used as the [`alias`](Document::alias) of this Document. Change it with `.alias()`.
     ///
     /// If the file does not exist, or if the create policy cannot be carried out, this function will return an error.
-    pub fn at<const N: usize>(
-        location: Folder<N>,
+    pub fn at(
+        location: impl Into<FolderPath>,
         filename: impl Display,
         create: Create,
-    ) -> Result<Self, Box<dyn Error>> {
-        let mut pathbuf = location.into_pathbuf_result(filename.to_string())?;
+    ) -> Result<Self, DocumentError> {
+        let pathbuf = location.into().to_pathbuf_result(filename.to_string())?;
         let original_name = pathbuf.name();
-        pathbuf = Document::setup(pathbuf, create, false)?;
-        Ok(Self {
-            alias: original_name,
-            pathbuf,
-            create_policy: create,
-        })
+        Document::with_backend(BackendRef::default(), pathbuf, original_name, create)
     }
 
     /// Create an instance of [`Document`](Document) from the full file path.
@@ -202,36 +411,187 @@ impl Document {
         path: impl Display,
         alias: impl Display,
         create: Create,
-    ) -> Result<Self, Box<dyn Error>> {
-        let mut pathbuf = PathBuf::from(path.to_string());
-        pathbuf = Document::setup(pathbuf, create, false)?;
+    ) -> Result<Self, DocumentError> {
+        Document::with_backend(
+            BackendRef::default(),
+            PathBuf::from(path.to_string()),
+            alias.to_string(),
+            create,
+        )
+    }
+
+    /// Create an instance of [`Document`](Document) at `pathbuf` whose operations go through `backend`.
+    pub(crate) fn with_backend(
+        backend: BackendRef,
+        pathbuf: PathBuf,
+        alias: String,
+        create: Create,
+    ) -> Result<Self, DocumentError> {
+        let (pathbuf, created) = Document::setup(backend.0.as_ref(), pathbuf, create, false)?;
         Ok(Self {
-            alias: alias.to_string(),
+            alias,
             pathbuf,
             create_policy: create,
+            durability: Durability::default(),
+            created,
+            staging: Staging::default(),
+            backend,
+            access: PhantomData,
         })
     }
-    fn open_file(&self, permissions: Mode) -> Result<File, Box<dyn Error>> {
-        match OpenOptions::new()
-            .read(permissions.readable())
-            .write(permissions.writable())
-            .append(permissions.appendable())
-            .open(self.pathbuf.clone())
-        {
-            Ok(file) => Ok(file),
-            Err(_) => Err(DocumentError::CouldNotOpenFile(self.path()))?,
+
+    /// Turn this Document into a [`Document<ReadOnly>`](ReadOnly), which only has the methods which read the file.
+    pub fn read_only(self) -> Document<ReadOnly> {
+        Document {
+            alias: self.alias,
+            pathbuf: self.pathbuf,
+            create_policy: self.create_policy,
+            durability: self.durability,
+            created: self.created,
+            staging: self.staging,
+            backend: self.backend,
+            access: PhantomData,
         }
     }
 
-    /// Launch the file with the default app. Equivalent to opening the file from a file manager.
+    /// Create an instance of [`Document`](Document) for every file in a [`Folder`](crate::Folder) location whose path matches a glob pattern.
     ///
-    /// Returns an error if the file could not be launched.
-    pub fn launch_with_default_app(&self) -> Result<&Self, Box<dyn Error>> {
-        if let Err(_) = open::that_detached(self.path()) {
-            Err(DocumentError::CouldNotLaunchFile(self.path()))?
-        } else {
-            Ok(self)
+    /// *location*: the [`Folder`](crate::Folder) or [`FolderPath`](FolderPath) to look in, e.g. `User(Pictures(["Screenshots"]))`.
+    ///
+    /// *pattern*: the glob pattern, relative to `location` with folders separated by "/". `*` matches any characters within a name,
+    /// `?` matches one character, `[abc]`, `[a-z]` and `[!abc]` match one character out of (or not out of) a set,
+    /// and `**` matches any number of folders. e.g. `"*.png"`, `"**/*.png"` or `"2024/report-[0-9][0-9].pdf"`.
+    ///
+    /// The [`alias`](Document::alias) of each Document is its path relative to `location`, e.g. "2024/report-01.pdf".
+    /// Files and folders whose names start with "." are only matched by parts of the pattern which start with "." too, e.g. `".config/**"` or `"*/.hidden.png"`,
+    /// not by `*`, `?` or `**`. The Documents are provided in alphabetical order with `Create::No`,
+    /// and can be given to [`with`](crate::with) as-is.
+    ///
+    /// ```ignore
+    /// with(Document::matching(User(Pictures(["Screenshots"])), "**/*.png"), |d| {
+    ///     for (alias, screenshot) in d {
+    ///         println!("{alias}: {} bytes", screenshot.bytes()?.len());
+    ///     }
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn matching(
+        location: impl Into<FolderPath>,
+        pattern: impl Display,
+    ) -> impl Iterator<Item = Result<Self, DocumentError>> {
+        let pattern = pattern.to_string();
+        // Hidden folders are only walked if the pattern can match something in them.
+        let include_hidden = pattern.split('/').any(|segment| segment.starts_with('.'));
+        let mut walk = location
+            .into()
+            .walk()
+            .include_hidden(include_hidden)
+            .sort_by(SortOrder::Name);
+        if let Some(depth) = glob_depth(&pattern) {
+            walk = walk.max_depth(depth);
+        }
+        walk.filter(move |document_result| match document_result {
+            Ok(document) => glob_match(&pattern, &document.alias),
+            Err(_) => true,
+        })
+    }
+
+    /// Redirect all reads and writes of this Document to a copy of the file, until the copy is committed with [`sync_file`](sync_file) and [`rename_durably`](rename_durably) or removed.
+    pub(crate) fn stage(&mut self) -> Result<(), DocumentError> {
+        let staged_pathbuf = temp_pathbuf_for(&self.pathbuf, "staged");
+        if let Err(error) = self.backend().copy(&self.pathbuf, &staged_pathbuf) {
+            let _ = self.backend().remove_file(&staged_pathbuf);
+            Err(DocumentError::CouldNotCreateFile(
+                staged_pathbuf.path(),
+                error,
+            ))?
         }
+        self.staging = Staging::new(staged_pathbuf);
+        Ok(())
+    }
+
+    fn write_file(&self, permissions: Mode, content: &[u8]) -> Result<(), DocumentError> {
+        let mut file = self.open_file(permissions)?;
+        // Written in one go rather than through a BufWriter, which would retry a failed write when dropped.
+        let result = file
+            .write_all(content)
+            .and_then(|_| file.flush())
+            .and_then(|_| match self.durability {
+                Durability::Flush => Ok(()),
+                Durability::SyncData => file.sync_data(),
+                Durability::SyncAll => file.sync_all(),
+            });
+        match result {
+            Ok(_) => Ok(()),
+            Err(error) => Err(DocumentError::CouldNotWriteFile(self.path(), error))?,
+        }
+    }
+
+    /// Set the [`Durability`](Durability) of write operations on this Document, i.e. whether to wait for written content to reach the disk.
+    pub fn set_durability(&mut self, durability: Durability) -> &mut Self {
+        self.durability = durability;
+        self
+    }
+
+    /// Move the file represented by this Document into another folder, keeping its name.
+    /// This Document then represents the moved file and keeps its [`alias`](Document::alias).
+    ///
+    /// If the folder is on another disk, the file is copied and then deleted.
+    ///
+    /// *location*: the [`Folder`](crate::Folder) or [`FolderPath`](FolderPath) to move the file into.
+    ///
+    /// *create*: the [`Create`](Create) policy at the destination, with the same meaning as in [`copy_to`](Document::copy_to).
+    ///
+    /// Returns an error if the `create` policy cannot be carried out, the move fails, or writes to this Document are [`staged`](crate::WithOptions::stage_writes).
+    pub fn move_to(
+        &mut self,
+        location: impl Into<FolderPath>,
+        create: Create,
+    ) -> Result<&mut Self, DocumentError> {
+        let destination = location.into().to_pathbuf_result(self.name())?;
+        self.pathbuf = self.transfer(destination, create, false)?;
+        Ok(self)
+    }
+
+    /// Rename the file represented by this Document within its folder.
+    /// This Document then represents the renamed file and keeps its [`alias`](Document::alias).
+    ///
+    /// *new_name*: the new name of the file with its file extension.
+    ///
+    /// *create*: the [`Create`](Create) policy for the new name, with the same meaning as in [`copy_to`](Document::copy_to).
+    /// e.g. `Create::AutoRenameIfExists` renames "draft.txt" to "final(1).txt" if "final.txt" already exists.
+    ///
+    /// Returns an error if the `create` policy cannot be carried out, the rename fails, or writes to this Document are [`staged`](crate::WithOptions::stage_writes).
+    pub fn rename(
+        &mut self,
+        new_name: impl Display,
+        create: Create,
+    ) -> Result<&mut Self, DocumentError> {
+        let destination = parent_folder_of(&self.pathbuf).join(new_name.to_string());
+        self.pathbuf = self.transfer(destination, create, false)?;
+        Ok(self)
+    }
+
+    /// Permanently delete the file represented by this Document.
+    ///
+    /// DANGER: the file does not go to the trash and cannot be recovered. Use [`trash`](Document::trash) instead to let the user restore it.
+    ///
+    /// Returns an error if the file could not be deleted, or writes to this Document are [`staged`](crate::WithOptions::stage_writes).
+    pub fn delete(&mut self) -> Result<(), DocumentError> {
+        self.check_not_staged(DocumentError::CouldNotDeleteFile)?;
+        match self.backend().remove_file(&self.pathbuf) {
+            Ok(_) => Ok(()),
+            Err(error) => Err(DocumentError::CouldNotDeleteFile(self.path(), error)),
+        }
+    }
+
+    /// Move the file represented by this Document to the trash, where it can be restored from. See [`TrashItem`](crate::TrashItem).
+    ///
+    /// Returns an error if the file could not be moved to the trash, or writes to this Document are [`staged`](crate::WithOptions::stage_writes).
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    pub fn trash(&mut self) -> Result<crate::TrashItem, DocumentError> {
+        self.check_not_staged(DocumentError::CouldNotTrash)?;
+        crate::trash::trash(self.backend(), &self.pathbuf)
     }
 
     /// Convert this Document to a [`File`](std::fs::File) (the standard library's type to represent a file). Useful if other functions or libraries expect a File,
@@ -240,8 +600,70 @@ impl Document {
     /// *permissions*: the [`Mode`](Mode) with which the file will be opened, can be `Mode::Read`, `Mode::Replace`, `Mode::Append`, `Mode::ReadReplace` and `Mode::ReadAppend`.
     ///
     /// Returns an error if the file cannot be opened.
-    pub fn file(&mut self, permissions: Mode) -> Result<File, Box<dyn Error>> {
-        self.open_file(permissions)
+    pub fn file(&mut self, permissions: Mode) -> Result<File, DocumentError> {
+        match self.backend().std_file(&self.io_pathbuf(), permissions) {
+            Ok(file) => Ok(file),
+            Err(error) => Err(DocumentError::CouldNotOpenFile(self.path(), error)),
+        }
+    }
+
+    /// Set the last modified and last accessed times of the file represented by this Document to now.
+    ///
+    /// Returns an error if the file cannot be opened or its times cannot be changed.
+    pub fn touch(&mut self) -> Result<&mut Self, DocumentError> {
+        let now = SystemTime::now();
+        self.set_times(Some(now), Some(now))
+    }
+
+    /// Set the last modified time of the file represented by this Document.
+    ///
+    /// Returns an error if the file cannot be opened or its times cannot be changed.
+    pub fn set_modified(&mut self, time: SystemTime) -> Result<&mut Self, DocumentError> {
+        self.set_times(None, Some(time))
+    }
+
+    /// Set the last accessed time of the file represented by this Document.
+    ///
+    /// Returns an error if the file cannot be opened or its times cannot be changed.
+    pub fn set_accessed(&mut self, time: SystemTime) -> Result<&mut Self, DocumentError> {
+        self.set_times(Some(time), None)
+    }
+
+    fn set_times(
+        &mut self,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> Result<&mut Self, DocumentError> {
+        match self
+            .backend()
+            .set_times(&self.io_pathbuf(), accessed, modified)
+        {
+            Ok(_) => Ok(self),
+            Err(error) => Err(DocumentError::CouldNotChangeMetadata(self.path(), error)),
+        }
+    }
+
+    /// Make the file represented by this Document read-only, or writable again.
+    ///
+    /// Note: on unix, making a file writable makes it writable for everyone. Use [`set_mode`](Document::set_mode) for finer control.
+    ///
+    /// Returns an error if the permissions of the file cannot be changed.
+    pub fn set_readonly(&mut self, readonly: bool) -> Result<&mut Self, DocumentError> {
+        match self.backend().set_readonly(&self.io_pathbuf(), readonly) {
+            Ok(_) => Ok(self),
+            Err(error) => Err(DocumentError::CouldNotChangeMetadata(self.path(), error)),
+        }
+    }
+
+    /// Set the unix permission bits of the file represented by this Document, e.g. `0o600` to let only its owner read and write it.
+    ///
+    /// Returns an error if the permissions of the file cannot be changed.
+    #[cfg(unix)]
+    pub fn set_mode(&mut self, mode: u32) -> Result<&mut Self, DocumentError> {
+        match self.backend().set_mode(&self.io_pathbuf(), mode) {
+            Ok(_) => Ok(self),
+            Err(error) => Err(DocumentError::CouldNotChangeMetadata(self.path(), error)),
+        }
     }
 
     /// Add content to the end of the file represented by this Document.
@@ -249,10 +671,9 @@ impl Document {
     /// *content*: bytes to be appended. If you have a string literal add `b` to convert it to bytes (`b"example"`); if you have an `&str` or `String` convert with `.as_bytes()`.
     /// If other libraries provide you with bytes, e.g. from a download operation, plug it in as-is.
     ///
-    /// Returns an error if the file cannot be opened or the write operation fails.
-    pub fn append(&mut self, content: &[u8]) -> Result<&mut Self, Box<dyn Error>> {
-        let file = self.open_file(Mode::Append)?;
-        BufWriter::new(file).write_all(content)?;
+    /// Returns an error if the file cannot be opened, or the write operation fails or cannot be flushed to the [`Durability`](Durability) of this Document.
+    pub fn append(&mut self, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        self.write_file(Mode::Append, content)?;
         Ok(self)
     }
 
@@ -263,25 +684,303 @@ impl Document {
     /// *content*: bytes to overwrite with. If you have a string literal add `b` to convert it to bytes (`b"example"`); if you have an `&str` or `String` convert with `.as_bytes()`.
     /// If other libraries provide you with bytes, e.g. from a download operation, you can plug it in as-is.
     ///
-    /// Returns an error if the file cannot be opened or the write operation fails.
-    pub fn replace_with(&mut self, content: &[u8]) -> Result<&mut Self, Box<dyn Error>> {
-        let file = self.open_file(Mode::Replace)?;
-        BufWriter::new(file).write_all(content)?;
+    /// Returns an error if the file cannot be opened, or the write operation fails or cannot be flushed to the [`Durability`](Durability) of this Document.
+    pub fn replace_with(&mut self, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        self.write_file(Mode::Replace, content)?;
+        Ok(self)
+    }
+
+    /// Replace the contents of the file represented by this Document without ever leaving it empty or half-written.
+    ///
+    /// The new content is first written to a temporary file in the same folder and flushed to disk,
+    /// then the temporary file is renamed over this file. If anything fails (even a crash or power loss) before the rename,
+    /// this file keeps its old content. The permissions of this file are kept.
+    ///
+    /// Note: if this Document is a symbolic link, the link itself is replaced by a regular file with the new content
+    /// (and the permissions of the file it pointed to). The file it pointed to is left untouched.
+    ///
+    /// Note: this always waits for the content to reach the disk, regardless of the [`Durability`](Durability) of this Document.
+    ///
+    /// Prefer this over [`replace_with`](Document::replace_with) for files which must never be lost, such as app settings.
+    ///
+    /// *content*: bytes to overwrite with. If you have a string literal add `b` to convert it to bytes (`b"example"`); if you have an `&str` or `String` convert with `.as_bytes()`.
+    ///
+    /// Returns an error if the temporary file cannot be created, or the write or rename operation fails.
+    pub fn replace_atomically(&mut self, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        write_atomically(self.backend(), &self.io_pathbuf(), content)?;
         Ok(self)
     }
 
+    /// Convert `value` to the [`Format`](Format) given by the file extension and replace the file represented by this Document with it.
+    ///
+    /// The file is replaced atomically, the same way as with [`replace_atomically`](Document::replace_atomically).
+    ///
+    /// Returns an error if the format is not known from the file extension or its cargo feature is not enabled (see [`Format`](Format)),
+    /// `value` cannot be converted, or the file cannot be replaced.
+    pub fn save<T: Serialize>(&mut self, value: &T) -> Result<&mut Self, DocumentError> {
+        self.save_as(value, self.format()?)
+    }
+
+    /// Same as [`save`](Document::save), but in the [`Format`](Format) given instead of the one from the file extension.
+    pub fn save_as<T: Serialize>(
+        &mut self,
+        value: &T,
+        format: Format,
+    ) -> Result<&mut Self, DocumentError> {
+        let content = format.serialize(self.path(), value)?;
+        self.replace_atomically(content.as_bytes())
+    }
+}
+
+impl<A: Access> Document<A> {
+    /// The path which reads and writes of this Document go to: the staging file while writes are staged, otherwise the file itself.
+    fn io_pathbuf(&self) -> PathBuf {
+        self.staging
+            .pathbuf()
+            .unwrap_or_else(|| self.pathbuf.clone())
+    }
+
+    /// The [`Backend`](crate::Backend) all operations of this Document go through.
+    pub(crate) fn backend(&self) -> &dyn Backend {
+        self.backend.0.as_ref()
+    }
+
+    fn open_file(&self, permissions: Mode) -> Result<Box<dyn FileHandle>, DocumentError> {
+        match self.backend().open(&self.io_pathbuf(), permissions) {
+            Ok(file) => Ok(file),
+            Err(error) => Err(DocumentError::CouldNotOpenFile(self.path(), error))?,
+        }
+    }
+
+    /// Open the file represented by this Document and keep it open for many reads and writes, which each of the other methods of Document would open the file again for.
+    /// See [`OpenDocument`](OpenDocument).
+    ///
+    /// *permissions*: the [`Mode`](Mode) with which the file will be opened, which the returned OpenDocument enforces.
+    ///
+    /// ```ignore
+    /// let mut file = document.open(Mode::ReadReplace)?;
+    /// file.write_at(0, b"HEADER")?;
+    /// let header = file.read_at(0, 6)?;
+    /// file.close()?;
+    /// ```
+    ///
+    /// A [`Document<ReadOnly>`](ReadOnly) can only open its file with `Mode::Read`.
+    ///
+    /// Returns an error if the file cannot be opened, or [`FileNotWritable`](DocumentError::FileNotWritable) if `permissions` writes to the file of a Document<ReadOnly>.
+    pub fn open(&self, permissions: Mode) -> Result<OpenDocument, DocumentError> {
+        if permissions.writable() && !is_writable::<A>() {
+            Err(DocumentError::FileNotWritable(self.path()))?
+        }
+        let file = self.open_file(permissions)?;
+        Ok(OpenDocument::new(
+            file,
+            permissions,
+            self.path(),
+            self.durability,
+        ))
+    }
+
+    /// Return `error` for this Document while its writes are staged, as its file only changes when they are committed.
+    fn check_not_staged(
+        &self,
+        error: fn(String, io::Error) -> DocumentError,
+    ) -> Result<(), DocumentError> {
+        if self.staging.pathbuf().is_some() {
+            Err(error(
+                self.path(),
+                io::Error::new(
+                    io::ErrorKind::ResourceBusy,
+                    "writes to the file are staged until the transaction commits",
+                ),
+            ))?
+        }
+        Ok(())
+    }
+
+    /// Whether `pathbuf` is the file represented by this Document, by path or (on unix) through a link.
+    fn is_same_file_as(&self, pathbuf: &Path) -> bool {
+        if pathbuf == self.pathbuf {
+            return true;
+        }
+        match (
+            self.backend().metadata(&self.pathbuf),
+            self.backend().metadata(pathbuf),
+        ) {
+            (Ok(metadata), Ok(other)) => metadata.same_file_as(&other),
+            _ => false,
+        }
+    }
+
+    /// Copy or move the file represented by this Document to `destination`, carrying out the `create` policy there first.
+    /// Returns the path the file ended up at.
+    fn transfer(
+        &self,
+        destination: PathBuf,
+        create: Create,
+        keep_source: bool,
+    ) -> Result<PathBuf, DocumentError> {
+        if !keep_source {
+            self.check_not_staged(DocumentError::CouldNotMoveFile)?;
+        }
+        let backend = self.backend();
+        let (destination, created) = Document::setup(backend, destination, create, false)?;
+        if keep_source && self.is_same_file_as(&destination) {
+            // Copying would truncate the destination, i.e. the file itself, before reading it.
+            Err(DocumentError::CouldNotCopyFile(
+                self.path(),
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the destination is the file itself",
+                ),
+            ))?
+        }
+        let copy_then_delete = || {
+            // Copies the staged content while writes are staged.
+            backend.copy(&self.io_pathbuf(), &destination)?;
+            if keep_source {
+                Ok(())
+            } else {
+                backend.remove_file(&self.pathbuf)
+            }
+        };
+        let result = if keep_source {
+            copy_then_delete()
+        } else {
+            match backend.rename(&self.pathbuf, &destination) {
+                Err(error) if error.kind() == io::ErrorKind::CrossesDevices => copy_then_delete(),
+                result => result,
+            }
+        };
+        match result {
+            Ok(_) => Ok(destination),
+            Err(error) => {
+                remove_created(backend, &created);
+                if keep_source {
+                    Err(DocumentError::CouldNotCopyFile(self.path(), error))
+                } else {
+                    Err(DocumentError::CouldNotMoveFile(self.path(), error))
+                }
+            }
+        }
+    }
+
+    /// Copy the file represented by this Document into another folder, keeping its name.
+    ///
+    /// *location*: the [`Folder`](crate::Folder) or [`FolderPath`](FolderPath) to copy the file into.
+    ///
+    /// *create*: the [`Create`](Create) policy for the copy. `Create::No` only replaces a file which already exists,
+    /// `Create::OnlyIfNotExists` replaces a file which already exists or creates a new one,
+    /// and `Create::AutoRenameIfExists` adds (1), (2), etc. to the name of the copy if a file of the same name already exists.
+    ///
+    /// Returns a new Document for the copy with the same [`alias`](Document::alias) as this Document, which can be written even if this Document is read-only,
+    /// or an error if the `create` policy cannot be carried out, the copy would be the file itself (e.g. copying into the same folder with `Create::No`),
+    /// or the copy fails.
+    ///
+    /// While writes to this Document are [`staged`](crate::WithOptions::stage_writes), the copy has the staged content.
+    pub fn copy_to(
+        &self,
+        location: impl Into<FolderPath>,
+        create: Create,
+    ) -> Result<Document, DocumentError> {
+        let destination = location.into().to_pathbuf_result(self.name())?;
+        let pathbuf = self.transfer(destination, create, true)?;
+        Ok(Document {
+            alias: self.alias.clone(),
+            pathbuf,
+            create_policy: create,
+            durability: self.durability,
+            created: Vec::new(),
+            staging: Staging::default(),
+            backend: self.backend.clone(),
+            access: PhantomData,
+        })
+    }
+
+    /// Launch the file with the default app. Equivalent to opening the file from a file manager.
+    ///
+    /// Returns an error if the file could not be launched.
+    pub fn launch_with_default_app(&self) -> Result<&Self, DocumentError> {
+        if let Err(error) = open::that_detached(self.path()) {
+            Err(DocumentError::CouldNotLaunchFile(self.path(), error))?
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Wait until no other process or thread holds an exclusive lock on the file represented by this Document, then take a shared lock on it.
+    /// The lock is held until the returned [`DocumentLock`](DocumentLock) is dropped.
+    ///
+    /// Returns an error if the file could not be opened or locked.
+    pub fn lock_shared(&self) -> Result<DocumentLock, DocumentError> {
+        self.lock(LockKind::Shared)
+    }
+
+    /// Wait until no other process or thread holds a lock on the file represented by this Document, then take an exclusive lock on it.
+    /// The lock is held until the returned [`DocumentLock`](DocumentLock) is dropped.
+    ///
+    /// ```ignore
+    /// let _lock = log.lock_exclusive()?;
+    /// log.append(b"Not interleaved with other processes\n")?;
+    /// ```
+    ///
+    /// Returns an error if the file could not be opened or locked.
+    pub fn lock_exclusive(&self) -> Result<DocumentLock, DocumentError> {
+        self.lock(LockKind::Exclusive)
+    }
+
+    /// Take a shared lock on the file represented by this Document if no other process or thread holds an exclusive lock on it.
+    /// Returns None instead of waiting if one does.
+    ///
+    /// Returns an error if the file could not be opened or locked.
+    pub fn try_lock_shared(&self) -> Result<Option<DocumentLock>, DocumentError> {
+        self.try_lock(LockKind::Shared)
+    }
+
+    /// Take an exclusive lock on the file represented by this Document if no other process or thread holds a lock on it.
+    /// Returns None instead of waiting if one does.
+    ///
+    /// Returns an error if the file could not be opened or locked.
+    pub fn try_lock_exclusive(&self) -> Result<Option<DocumentLock>, DocumentError> {
+        self.try_lock(LockKind::Exclusive)
+    }
+
+    /// Wait for and take a lock of `kind` on the file itself, even while writes are staged, since that is the file other processes lock.
+    pub(crate) fn lock(&self, kind: LockKind) -> Result<DocumentLock, DocumentError> {
+        let mut file = self.open_for_locking()?;
+        match file.lock(kind) {
+            Ok(_) => Ok(DocumentLock::new(file, kind)),
+            Err(error) => Err(DocumentError::CouldNotLockFile(self.path(), error))?,
+        }
+    }
+
+    fn try_lock(&self, kind: LockKind) -> Result<Option<DocumentLock>, DocumentError> {
+        let mut file = self.open_for_locking()?;
+        match file.try_lock(kind) {
+            Ok(true) => Ok(Some(DocumentLock::new(file, kind))),
+            Ok(false) => Ok(None),
+            Err(error) => Err(DocumentError::CouldNotLockFile(self.path(), error))?,
+        }
+    }
+
+    fn open_for_locking(&self) -> Result<Box<dyn FileHandle>, DocumentError> {
+        match self.backend().open(&self.pathbuf, Mode::Read) {
+            Ok(file) => Ok(file),
+            Err(error) => Err(DocumentError::CouldNotOpenFile(self.path(), error))?,
+        }
+    }
+
     /// Returns an iterator over the lines of the file represented by this Document.
     ///
     /// Returns an error if the file could not be opened in read mode.
     ///
     /// Useful for processing the contents of file line by line.
     ///
-    /// ```
+    /// ```ignore
     /// for line in document.lines().expect("Could not read lines")  {
     ///     println!("{line}");
     /// }
     /// ```
-    pub fn lines(&self) -> Result<Lines<BufReader<File>>, Box<dyn Error>> {
+    pub fn lines(&self) -> Result<Lines<BufReader<Box<dyn FileHandle>>>, DocumentError> {
         let file = self.open_file(Mode::Read)?;
         Ok(BufReader::new(file).lines())
     }
@@ -291,15 +990,113 @@ impl Document {
     /// Returns an error if the file could not be opened in read mode or
     /// its content could not be written to a String.
     ///
-    /// ```
+    /// ```ignore
     /// let file_content = document.content().expect("Could not read content");
     /// println!("{file_content}");
     /// ```
-    pub fn content(&self) -> Result<String, Box<dyn Error>> {
+    pub fn content(&self) -> Result<String, DocumentError> {
         let mut file = self.open_file(Mode::Read)?;
         let mut string = String::new();
-        file.read_to_string(&mut string)?;
-        Ok(string)
+        match file.read_to_string(&mut string) {
+            Ok(_) => Ok(string),
+            Err(error) => Err(DocumentError::CouldNotReadFile(self.path(), error)),
+        }
+    }
+
+    /// Returns the contents of the file represented by this Document as bytes.
+    ///
+    /// Unlike [`content`](Document::content), this works for any file, including pictures, PDFs and archives.
+    ///
+    /// Returns an error if the file could not be opened in read mode or could not be read.
+    ///
+    /// ```ignore
+    /// let png = document.bytes().expect("Could not read bytes");
+    /// println!("{} bytes", png.len());
+    /// ```
+    pub fn bytes(&self) -> Result<Vec<u8>, DocumentError> {
+        let mut file = self.open_file(Mode::Read)?;
+        let mut bytes = Vec::new();
+        match file.read_to_end(&mut bytes) {
+            Ok(_) => Ok(bytes),
+            Err(error) => Err(DocumentError::CouldNotReadFile(self.path(), error)),
+        }
+    }
+
+    /// Returns up to `len` bytes of the file represented by this Document, starting `offset` bytes from the start of the file.
+    ///
+    /// Fewer than `len` bytes are returned if the end of the file is reached first.
+    ///
+    /// Returns an error if the file could not be opened in read mode or could not be read.
+    pub fn bytes_range(&self, offset: u64, len: usize) -> Result<Vec<u8>, DocumentError> {
+        let mut file = self.open_file(Mode::Read)?;
+        let mut bytes = Vec::with_capacity(len);
+        match file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.take(len as u64).read_to_end(&mut bytes))
+        {
+            Ok(_) => Ok(bytes),
+            Err(error) => Err(DocumentError::CouldNotReadFile(self.path(), error)),
+        }
+    }
+
+    /// Returns an iterator over the contents of the file represented by this Document in chunks of `chunk_size` bytes.
+    ///
+    /// Useful for processing large files without loading them into memory all at once.
+    ///
+    /// A `chunk_size` of 0 is treated as 1, since empty chunks would never reach the end of the file.
+    ///
+    /// Returns an error if the file could not be opened in read mode.
+    ///
+    /// ```ignore
+    /// for chunk in document.chunks(4096).expect("Could not read chunks") {
+    ///     upload(&chunk?);
+    /// }
+    /// ```
+    pub fn chunks(&self, chunk_size: usize) -> Result<Chunks, DocumentError> {
+        let file = self.open_file(Mode::Read)?;
+        Ok(Chunks::new(file, chunk_size))
+    }
+
+    /// Copy the contents of the file represented by this Document into `writer`, without loading it into memory all at once.
+    ///
+    /// *writer*: anything that implements [`Write`](std::io::Write), e.g. a [`File`](std::fs::File), a `Vec<u8>` or a network stream.
+    ///
+    /// Returns the number of bytes copied, or an error if the file could not be opened in read mode or the copy fails.
+    pub fn read_into(&self, writer: &mut impl Write) -> Result<u64, DocumentError> {
+        let mut file = self.open_file(Mode::Read)?;
+        match io::copy(&mut file, writer) {
+            Ok(bytes_copied) => Ok(bytes_copied),
+            Err(error) => Err(DocumentError::CouldNotReadFile(self.path(), error)),
+        }
+    }
+
+    /// Read the file represented by this Document and convert its content into a value of type `T`,
+    /// in the [`Format`](Format) given by the file extension (e.g. "settings.toml" is read as TOML).
+    ///
+    /// ```ignore
+    /// #[derive(Deserialize)]
+    /// struct Settings { theme: String }
+    ///
+    /// let settings: Settings = Document::at(config, "settings.toml", Create::No)?.load()?;
+    /// ```
+    ///
+    /// Returns an error if the format is not known from the file extension or its cargo feature is not enabled (see [`Format`](Format)),
+    /// the file cannot be read, or its content is not a valid `T`.
+    /// Errors from parsing carry the line and column where parsing failed.
+    pub fn load<T: DeserializeOwned>(&self) -> Result<T, DocumentError> {
+        self.load_as(self.format()?)
+    }
+
+    /// Same as [`load`](Document::load), but in the [`Format`](Format) given instead of the one from the file extension.
+    pub fn load_as<T: DeserializeOwned>(&self, format: Format) -> Result<T, DocumentError> {
+        format.deserialize(self.path(), &self.content()?)
+    }
+
+    pub(crate) fn format(&self) -> Result<Format, DocumentError> {
+        match Format::from_extension(&self.extension()) {
+            Some(format) => Ok(format),
+            None => Err(DocumentError::UnknownFormat(self.path())),
+        }
     }
 
     /// The file extension of the file represented by this Document.
@@ -316,15 +1113,15 @@ impl Document {
     }
 }
 
-#[ext(pub)]
-impl Result<Document, Box<dyn Error>> {
+#[ext(pub, name = ResultDocumentExt)]
+impl Result<Document, DocumentError> {
     /// Sets the alias of this Document if this Document has created successfully. Use "_" to instruct [`with(...)`](with) to skip adding this Document to the [`DocumentMap`](DocumentMap).
     ///
     /// Note: the alias is used to identify this Document in a [DocumentMap](DocumentMap).
     /// Do not provide the same alias for multiple Documents.
     ///
     /// Returns an error if this Document has not been created successfully.
-    fn alias(self, alias: &str) -> Result<Document, Box<dyn Error>> {
+    fn alias(self, alias: &str) -> Result<Document, DocumentError> {
         match self {
             Ok(mut document) => {
                 document.alias = String::from(alias);
@@ -334,6 +1131,26 @@ impl Result<Document, Box<dyn Error>> {
         }
     }
 
+    /// Turns this Document into a [`Document<ReadOnly>`](ReadOnly) if this Document has been created successfully. See [`Document::read_only`](Document::read_only).
+    ///
+    /// Returns an error if this Document has not been created successfully.
+    fn read_only(self) -> Result<Document<ReadOnly>, DocumentError> {
+        self.map(Document::read_only)
+    }
+
+    /// Sets the [`Durability`](Durability) of write operations on this Document if this Document has been created successfully.
+    ///
+    /// Returns an error if this Document has not been created successfully.
+    fn durability(self, durability: Durability) -> Result<Document, DocumentError> {
+        match self {
+            Ok(mut document) => {
+                document.durability = durability;
+                Ok(document)
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     /// Suggest a rename of this Document if there is already a file at that path.
     ///
     /// e.g. if `picture.png` already exists, this will return `picture(1).png`. If `picture(1).png` already exists, this will return `picture(2).png`, etc.
@@ -344,31 +1161,31 @@ impl Result<Document, Box<dyn Error>> {
     fn suggest_rename(&self) -> String {
         match self {
             Ok(document) => {
-                match Document::setup(document.pathbuf.clone(), Create::AutoRenameIfExists, true) {
-                    Ok(new_name) => new_name.path(),
+                match Document::setup(
+                    document.backend(),
+                    document.pathbuf.clone(),
+                    Create::AutoRenameIfExists,
+                    true,
+                ) {
+                    Ok((new_name, _)) => new_name.path(),
                     Err(error) => {
                         eprintln!("{}", error);
                         "".to_string()
                     }
                 }
             }
-            Err(error) => match error.downcast_ref::<DocumentError>() {
-                Some(document_error) => match document_error {
-                    DocumentError::FileNotFound(path) => path.clone(),
-                    _ => "".to_string(),
-                },
-                None => "".to_string(),
-            },
+            Err(DocumentError::FileNotFound(path)) => path.clone(),
+            Err(_) => "".to_string(),
         }
     }
 }
 
-#[ext(pub)]
-impl Lines<BufReader<File>> {
+#[ext(pub, name = LinesBufReaderFileExt)]
+impl Lines<BufReader<Box<dyn FileHandle>>> {
     /// Print out this file line by line.
     ///
     /// Returns an error if the line cannot be read.
-    fn print(self) -> Result<(), Box<dyn Error>> {
+    fn print(self) -> io::Result<()> {
         for line in self {
             println!("{}", line?);
         }
@@ -376,7 +1193,7 @@ impl Lines<BufReader<File>> {
     }
 }
 
-impl FileSystemEntity for Document {
+impl<A: Access> FileSystemEntity for Document<A> {
     fn name(&self) -> String {
         self.pathbuf
             .clone()
@@ -390,11 +1207,17 @@ impl FileSystemEntity for Document {
         self.pathbuf.display().to_string()
     }
     fn exists(&self) -> bool {
-        self.pathbuf.exists()
+        self.backend().exists(&self.pathbuf)
+    }
+    fn metadata(&self) -> Result<Metadata, DocumentError> {
+        match self.backend().metadata(&self.io_pathbuf()) {
+            Ok(metadata) => Ok(metadata),
+            Err(error) => Err(DocumentError::CouldNotReadMetadata(self.path(), error)),
+        }
     }
 }
 
-impl FileSystemEntity for Result<Document, Box<dyn Error>> {
+impl<A: Access> FileSystemEntity for Result<Document<A>, DocumentError> {
     fn exists(&self) -> bool {
         match self {
             Ok(document) => document.exists(),
@@ -1,10 +1,21 @@
-use std::{error::Error, fmt::Display};
+use std::{error::Error, fmt::Display, io};
+
+use crate::{Format, ParseError};
 
 /// This library's error types.
 ///
-/// Note: functions in this library will not actually return this concrete error type.
-/// Instead a Box<dyn Error> will be returned. Print it to the console to see a description of the error.
-#[derive(Debug, Clone, PartialEq, Hash)]
+/// Errors caused by the operating system, such as a missing permission or a full disk, carry the path of the file or folder
+/// and the original [`io::Error`](std::io::Error), which is available through [`source()`](std::error::Error::source).
+/// The variant tells which operation failed, and [`kind()`](DocumentError::kind) tells why.
+///
+/// ```ignore
+/// match Document::at(User(Documents([])), "notes.txt", Create::No) {
+///     Ok(document) => println!("{document}"),
+///     Err(error) if error.kind() == DocumentErrorKind::PermissionDenied => println!("Not allowed to open {}", error.path().unwrap()),
+///     Err(error) => println!("{error}"),
+/// }
+/// ```
+#[derive(Debug)]
 pub enum DocumentError {
     /// "User directories not found"
     UserDirsNotFound,
@@ -16,22 +27,208 @@ pub enum DocumentError {
     DownloadsDirNotFound,
     /// "Documents directory not found"
     DocumentsDirNotFound,
+    /// "Desktop directory not found"
+    DesktopDirNotFound,
+    /// "Music directory not found"
+    MusicDirNotFound,
+    /// "Public directory not found"
+    PublicDirNotFound,
+    /// "Templates directory not found"
+    TemplatesDirNotFound,
+    /// "Fonts directory not found"
+    FontsDirNotFound,
     /// "Project directories not found"
     ProjectDirsNotFound,
+    /// "Project state directory not found"
+    StateDirNotFound,
+    /// "Project runtime directory not found"
+    RuntimeDirNotFound,
     /// "File not found: (file path)"
     FileNotFound(String),
-    /// "Could not create file: (file path)"
-    CouldNotCreateFile(String),
-    /// "Could not create parent folder: (parent directory path)"
-    CouldNotCreateParentFolder(String),
-    /// "Could not launch file with default app: (file path)"
-    CouldNotLaunchFile(String),
-    /// "Could not open file: (file path)"
-    CouldNotOpenFile(String),
+    /// "Could not create file: (file path) ((reason))"
+    CouldNotCreateFile(String, io::Error),
+    /// "Could not create parent folder: (parent directory path) ((reason))"
+    CouldNotCreateParentFolder(String, io::Error),
+    /// "Could not launch file with default app: (file path) ((reason))"
+    CouldNotLaunchFile(String, io::Error),
+    /// "Could not open file: (file path) ((reason))"
+    CouldNotOpenFile(String, io::Error),
     /// "File not writable: (file path)"
     FileNotWritable(String),
     /// "File not open: (file path)"
     FileNotOpen(String),
+    /// "File not readable: (file path)"
+    FileNotReadable(String),
+    /// "Could not write to file: (file path) ((reason))"
+    CouldNotWriteFile(String, io::Error),
+    /// "Could not read file: (file path) ((reason))"
+    CouldNotReadFile(String, io::Error),
+    /// "Could not read folder: (folder path) ((reason))"
+    CouldNotReadFolder(String, io::Error),
+    /// "Could not copy file: (file path) ((reason))"
+    CouldNotCopyFile(String, io::Error),
+    /// "Could not move file: (file path) ((reason))"
+    CouldNotMoveFile(String, io::Error),
+    /// "Could not delete file: (file path) ((reason))"
+    CouldNotDeleteFile(String, io::Error),
+    /// "Could not move to the trash: (path) ((reason))"
+    CouldNotTrash(String, io::Error),
+    /// "Could not restore from the trash: (original path) ((reason))"
+    CouldNotRestore(String, io::Error),
+    /// "Could not read metadata: (path) ((reason))"
+    CouldNotReadMetadata(String, io::Error),
+    /// "Could not change metadata: (path) ((reason))"
+    CouldNotChangeMetadata(String, io::Error),
+    /// "Could not lock file: (file path) ((reason))"
+    CouldNotLockFile(String, io::Error),
+    /// "Unknown file format: (file path)"
+    UnknownFormat(String),
+    /// "Format not enabled: (file path) (enable the "(feature)" feature of documents to use (format))"
+    FormatNotEnabled(String, Format),
+    /// "Could not parse file: (file path) (invalid (format) at line (line), column (column): (reason))"
+    CouldNotParseFile(String, ParseError),
+    /// "Could not serialize value for file: (file path) ((reason))"
+    CouldNotSerializeValue(String, String),
+    /// "Settings file has a newer schema version than supported: (file path) (version (version))"
+    SchemaVersionTooNew(String, u64),
+    /// "Could not migrate settings file from schema version (version): (file path) ((reason))"
+    CouldNotMigrateFile(String, u64, Box<dyn Error + Send + Sync>),
+}
+
+/// Why a [`DocumentError`](DocumentError) happened, independent of which operation failed.
+///
+/// New kinds may be added in the future, so always include a `_ =>` arm when matching on this type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DocumentErrorKind {
+    /// A well-known folder does not exist on this device.
+    FolderNotFound,
+    /// The file or folder does not exist.
+    NotFound,
+    /// A file or folder already exists at the path.
+    AlreadyExists,
+    /// The operating system does not allow this operation on the file or folder.
+    PermissionDenied,
+    /// The path points to a folder where a file was expected.
+    IsADirectory,
+    /// A part of the path which should be a folder is not a folder.
+    NotADirectory,
+    /// The disk is full.
+    StorageFull,
+    /// The file or folder is on a read-only filesystem.
+    ReadOnlyFilesystem,
+    /// The content of the file is not valid for this operation, e.g. it is not valid UTF-8 text.
+    InvalidData,
+    /// The file is not open with a [`Mode`](crate::Mode) which permits this operation.
+    WrongMode,
+    /// The file format is not known from the file extension, or its cargo feature is not enabled. See [`Format`](crate::Format).
+    UnsupportedFormat,
+    /// Any other reason. Check [`source()`](std::error::Error::source) for details.
+    Other,
+}
+
+impl From<io::ErrorKind> for DocumentErrorKind {
+    fn from(kind: io::ErrorKind) -> Self {
+        match kind {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::AlreadyExists => Self::AlreadyExists,
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            io::ErrorKind::IsADirectory => Self::IsADirectory,
+            io::ErrorKind::NotADirectory => Self::NotADirectory,
+            io::ErrorKind::StorageFull => Self::StorageFull,
+            io::ErrorKind::ReadOnlyFilesystem => Self::ReadOnlyFilesystem,
+            io::ErrorKind::InvalidData => Self::InvalidData,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl DocumentError {
+    /// Why this error happened. See [`DocumentErrorKind`](DocumentErrorKind).
+    pub fn kind(&self) -> DocumentErrorKind {
+        match self {
+            Self::UserDirsNotFound
+            | Self::PicturesDirNotFound
+            | Self::VideosDirNotFound
+            | Self::DownloadsDirNotFound
+            | Self::DocumentsDirNotFound
+            | Self::DesktopDirNotFound
+            | Self::MusicDirNotFound
+            | Self::PublicDirNotFound
+            | Self::TemplatesDirNotFound
+            | Self::FontsDirNotFound
+            | Self::ProjectDirsNotFound
+            | Self::StateDirNotFound
+            | Self::RuntimeDirNotFound => DocumentErrorKind::FolderNotFound,
+            Self::FileNotFound(_) => DocumentErrorKind::NotFound,
+            Self::FileNotWritable(_) | Self::FileNotOpen(_) | Self::FileNotReadable(_) => {
+                DocumentErrorKind::WrongMode
+            }
+            Self::UnknownFormat(_) | Self::FormatNotEnabled(_, _) => {
+                DocumentErrorKind::UnsupportedFormat
+            }
+            Self::CouldNotParseFile(_, _)
+            | Self::CouldNotSerializeValue(_, _)
+            | Self::SchemaVersionTooNew(_, _) => DocumentErrorKind::InvalidData,
+            _ => match self.io_error() {
+                Some(error) => error.kind().into(),
+                None => DocumentErrorKind::Other,
+            },
+        }
+    }
+
+    /// The path of the file or folder this error is about. Returns None if this error is not about a specific path.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            Self::FileNotFound(path)
+            | Self::CouldNotCreateFile(path, _)
+            | Self::CouldNotCreateParentFolder(path, _)
+            | Self::CouldNotLaunchFile(path, _)
+            | Self::CouldNotOpenFile(path, _)
+            | Self::FileNotWritable(path)
+            | Self::FileNotOpen(path)
+            | Self::FileNotReadable(path)
+            | Self::CouldNotWriteFile(path, _)
+            | Self::CouldNotReadFile(path, _)
+            | Self::CouldNotReadFolder(path, _)
+            | Self::CouldNotCopyFile(path, _)
+            | Self::CouldNotMoveFile(path, _)
+            | Self::CouldNotDeleteFile(path, _)
+            | Self::CouldNotTrash(path, _)
+            | Self::CouldNotRestore(path, _)
+            | Self::CouldNotReadMetadata(path, _)
+            | Self::CouldNotChangeMetadata(path, _)
+            | Self::CouldNotLockFile(path, _)
+            | Self::UnknownFormat(path)
+            | Self::FormatNotEnabled(path, _)
+            | Self::CouldNotParseFile(path, _)
+            | Self::CouldNotSerializeValue(path, _)
+            | Self::SchemaVersionTooNew(path, _)
+            | Self::CouldNotMigrateFile(path, _, _) => Some(path),
+            _ => None,
+        }
+    }
+
+    fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            Self::CouldNotCreateFile(_, error)
+            | Self::CouldNotCreateParentFolder(_, error)
+            | Self::CouldNotLaunchFile(_, error)
+            | Self::CouldNotOpenFile(_, error)
+            | Self::CouldNotWriteFile(_, error)
+            | Self::CouldNotReadFile(_, error)
+            | Self::CouldNotReadFolder(_, error)
+            | Self::CouldNotCopyFile(_, error)
+            | Self::CouldNotMoveFile(_, error)
+            | Self::CouldNotDeleteFile(_, error)
+            | Self::CouldNotTrash(_, error)
+            | Self::CouldNotRestore(_, error)
+            | Self::CouldNotReadMetadata(_, error)
+            | Self::CouldNotChangeMetadata(_, error)
+            | Self::CouldNotLockFile(_, error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 impl Display for DocumentError {
@@ -42,27 +239,91 @@ impl Display for DocumentError {
             Self::VideosDirNotFound => "Videos directory not found".to_string(),
             Self::DownloadsDirNotFound => "Downloads directory not found".to_string(),
             Self::FileNotFound(file_path) => "File not found: ".to_string() + file_path,
-            Self::CouldNotCreateFile(file_path) => {
-                "Could not create file: ".to_string() + file_path
+            Self::CouldNotCreateFile(file_path, error) => {
+                format!("Could not create file: {file_path} ({error})")
             }
-            Self::CouldNotCreateParentFolder(parent_folder_path) => {
-                "Could not create parent folder: ".to_string() + parent_folder_path
+            Self::CouldNotCreateParentFolder(parent_folder_path, error) => {
+                format!("Could not create parent folder: {parent_folder_path} ({error})")
             }
-            Self::CouldNotLaunchFile(file_path) => {
-                "Could not launch file with default app: ".to_string() + file_path
+            Self::CouldNotLaunchFile(file_path, error) => {
+                format!("Could not launch file with default app: {file_path} ({error})")
             }
             Self::ProjectDirsNotFound => "Project directories not found".to_string(),
-            Self::CouldNotOpenFile(file_path) => "Could not open file: ".to_string() + file_path,
+            Self::StateDirNotFound => "Project state directory not found".to_string(),
+            Self::RuntimeDirNotFound => "Project runtime directory not found".to_string(),
+            Self::CouldNotOpenFile(file_path, error) => {
+                format!("Could not open file: {file_path} ({error})")
+            }
             Self::DocumentsDirNotFound => "Documents directory not found".to_string(),
+            Self::DesktopDirNotFound => "Desktop directory not found".to_string(),
+            Self::MusicDirNotFound => "Music directory not found".to_string(),
+            Self::PublicDirNotFound => "Public directory not found".to_string(),
+            Self::TemplatesDirNotFound => "Templates directory not found".to_string(),
+            Self::FontsDirNotFound => "Fonts directory not found".to_string(),
             Self::FileNotWritable(file_path) => "File not writable: ".to_string() + file_path,
             Self::FileNotOpen(file_path) => "File not open: ".to_string() + file_path,
+            Self::FileNotReadable(file_path) => "File not readable: ".to_string() + file_path,
+            Self::CouldNotWriteFile(file_path, error) => {
+                format!("Could not write to file: {file_path} ({error})")
+            }
+            Self::CouldNotReadFile(file_path, error) => {
+                format!("Could not read file: {file_path} ({error})")
+            }
+            Self::CouldNotReadFolder(folder_path, error) => {
+                format!("Could not read folder: {folder_path} ({error})")
+            }
+            Self::CouldNotCopyFile(file_path, error) => {
+                format!("Could not copy file: {file_path} ({error})")
+            }
+            Self::CouldNotMoveFile(file_path, error) => {
+                format!("Could not move file: {file_path} ({error})")
+            }
+            Self::CouldNotDeleteFile(file_path, error) => {
+                format!("Could not delete file: {file_path} ({error})")
+            }
+            Self::CouldNotTrash(path, error) => {
+                format!("Could not move to the trash: {path} ({error})")
+            }
+            Self::CouldNotRestore(path, error) => {
+                format!("Could not restore from the trash: {path} ({error})")
+            }
+            Self::CouldNotReadMetadata(path, error) => {
+                format!("Could not read metadata: {path} ({error})")
+            }
+            Self::CouldNotChangeMetadata(path, error) => {
+                format!("Could not change metadata: {path} ({error})")
+            }
+            Self::CouldNotLockFile(file_path, error) => {
+                format!("Could not lock file: {file_path} ({error})")
+            }
+            Self::UnknownFormat(file_path) => "Unknown file format: ".to_string() + file_path,
+            Self::FormatNotEnabled(file_path, format) => format!(
+                "Format not enabled: {file_path} (enable the \"{}\" feature of documents to use {format})",
+                format.feature()
+            ),
+            Self::CouldNotParseFile(file_path, error) => {
+                format!("Could not parse file: {file_path} ({error})")
+            }
+            Self::CouldNotSerializeValue(file_path, reason) => {
+                format!("Could not serialize value for file: {file_path} ({reason})")
+            }
+            Self::SchemaVersionTooNew(file_path, version) => format!(
+                "Settings file has a newer schema version than supported: {file_path} (version {version})"
+            ),
+            Self::CouldNotMigrateFile(file_path, version, reason) => format!(
+                "Could not migrate settings file from schema version {version}: {file_path} ({reason})"
+            ),
         };
         f.pad(msg.as_str())
     }
 }
 
 impl Error for DocumentError {
-    fn description(&self) -> &str {
-        "Document error"
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::CouldNotParseFile(_, error) => Some(error),
+            Self::CouldNotMigrateFile(_, _, error) => Some(error.as_ref()),
+            _ => self.io_error().map(|error| error as &(dyn Error + 'static)),
+        }
     }
 }
@@ -1,43 +1,202 @@
 use std::{
-    collections::{hash_map::IntoIter, HashMap},
+    collections::{
+        hash_map::{IntoIter, Iter, IterMut},
+        HashMap,
+    },
     fmt::Display,
     ops::{Index, IndexMut},
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::Document;
+use crate::{Document, ReadOnly};
 
 /// A type that wraps a HashMap between a String and Documents. Access the Documents with any type of index that can be converted to a String.
 ///
 /// An instance of this type is provided by [`with`](with) containing all of the [`Document`](Document)s
 /// given in the `documents` parameter as the values, and their respective [`alias`](Document::alias)es as keys.
+///
+/// Indexing with an alias which is not in the map panics, listing the aliases which are. Use [`get`](DocumentMap::get) to get an Option instead.
+///
+/// ```ignore
+/// with(documents, |mut d| {
+///     for (alias, document) in &d {
+///         println!("{alias}: {document}");
+///     }
+///     for document in d.filter_mut(|document| document.extension() == "log") {
+///         document.append(b"Done\n")?;
+///     }
+///     Ok(())
+/// });
+/// ```
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DocumentMap(pub(crate) HashMap<String, Document>);
 
-impl<'a, Str> Index<Str> for DocumentMap
+impl DocumentMap {
+    /// The Document with the alias `alias`, or None if there is none.
+    pub fn get(&self, alias: impl Display) -> Option<&Document> {
+        self.0.get(alias.to_string().as_str())
+    }
+
+    /// The Document with the alias `alias` for write operations, or None if there is none.
+    pub fn get_mut(&mut self, alias: impl Display) -> Option<&mut Document> {
+        self.0.get_mut(alias.to_string().as_str())
+    }
+
+    /// Whether there is a Document with the alias `alias`.
+    pub fn contains(&self, alias: impl Display) -> bool {
+        self.0.contains_key(alias.to_string().as_str())
+    }
+
+    /// Add `document` under its [`alias`](Document::alias). Returns the Document which had this alias before, if any.
+    pub fn insert(&mut self, document: Document) -> Option<Document> {
+        self.0.insert(document.alias.clone(), document)
+    }
+
+    /// Take the Document with the alias `alias` out of this map. Returns None if there is none.
+    pub fn remove(&mut self, alias: impl Display) -> Option<Document> {
+        self.0.remove(alias.to_string().as_str())
+    }
+
+    /// The number of Documents in this map.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Whether there are no Documents in this map.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The aliases of the Documents in this map, sorted.
+    pub fn aliases(&self) -> Vec<&str> {
+        let mut aliases: Vec<_> = self.0.keys().map(String::as_str).collect();
+        aliases.sort_unstable();
+        aliases
+    }
+
+    /// An iterator over the aliases and Documents in this map, in no particular order.
+    pub fn iter(&self) -> Iter<'_, String, Document> {
+        self.0.iter()
+    }
+
+    /// An iterator over the aliases and Documents in this map for write operations, in no particular order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, String, Document> {
+        self.0.iter_mut()
+    }
+
+    /// An iterator over the Documents in this map for which `predicate` returns true, in no particular order.
+    pub fn filter<'a>(
+        &'a self,
+        mut predicate: impl FnMut(&Document) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Document> + 'a {
+        self.0.values().filter(move |document| predicate(document))
+    }
+
+    /// An iterator over the Documents in this map for which `predicate` returns true for write operations, in no particular order.
+    pub fn filter_mut<'a>(
+        &'a mut self,
+        mut predicate: impl FnMut(&Document) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a mut Document> + 'a {
+        self.0
+            .values_mut()
+            .filter(move |document| predicate(document))
+    }
+
+    /// An iterator over the Documents in this map whose file extension is `extension`, e.g. "txt" or ".txt", ignoring case.
+    pub fn with_extension<'a>(
+        &'a self,
+        extension: &'a str,
+    ) -> impl Iterator<Item = &'a Document> + 'a {
+        let extension = extension.trim_start_matches('.');
+        self.filter(move |document| document.extension().eq_ignore_ascii_case(extension))
+    }
+
+    /// Panic because there is no Document with the alias `alias`, listing the aliases there are.
+    fn missing(&self, alias: &str) -> ! {
+        let aliases = self.aliases();
+        if aliases.is_empty() {
+            panic!("No document with the alias \"{alias}\": the DocumentMap is empty")
+        }
+        panic!(
+            "No document with the alias \"{alias}\": the aliases are \"{}\"",
+            aliases.join("\", \"")
+        )
+    }
+
+    /// A [`Document<ReadOnly>`](ReadOnly) for the Document with the alias `alias`, which only has the methods of [`Document`](Document) which read the file.
+    /// Useful to hand a Document to code which should not be able to change it.
+    ///
+    /// The Document stays in this map, so its alias keeps working, and the view reads what is written through the map, including [`staged`](crate::WithOptions::stage_writes) writes.
+    /// This is also how to use a read-only Document in [`with`](crate::with): give it the Document, then get a view with this method.
+    ///
+    /// ```ignore
+    /// with(
+    ///     [
+    ///         Document::at(User(Documents([])), "template.txt", Create::No),
+    ///         Document::at(User(Documents([])), "letter.txt", Create::OnlyIfNotExists),
+    ///     ],
+    ///     |mut d| {
+    ///         let template = d.read_only("template.txt");
+    ///         d["letter.txt"].replace_with(template.content()?.as_bytes())?;
+    ///         Ok(())
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// Panics if there is no Document with this alias, like indexing does.
+    pub fn read_only(&self, alias: impl Display) -> Document<ReadOnly> {
+        self[alias].clone().read_only()
+    }
+}
+
+impl<Str> Index<Str> for DocumentMap
 where
     Str: Display,
 {
     type Output = Document;
     fn index(&self, index: Str) -> &Self::Output {
-        &self.0[index.to_string().as_str()]
+        let alias = index.to_string();
+        match self.0.get(alias.as_str()) {
+            Some(document) => document,
+            None => self.missing(&alias),
+        }
     }
 }
 
-impl<'a, Str> IndexMut<Str> for DocumentMap
+impl<Str> IndexMut<Str> for DocumentMap
 where
     Str: Display,
 {
     fn index_mut(&mut self, index: Str) -> &mut Self::Output {
-        self.0.get_mut(index.to_string().as_str()).unwrap()
+        let alias = index.to_string();
+        if !self.0.contains_key(alias.as_str()) {
+            self.missing(&alias)
+        }
+        self.0.get_mut(alias.as_str()).unwrap()
     }
 }
 
-impl<'a> IntoIterator for DocumentMap {
+impl IntoIterator for DocumentMap {
     type Item = (String, Document);
     type IntoIter = IntoIter<String, Document>;
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
     }
 }
+
+impl<'a> IntoIterator for &'a DocumentMap {
+    type Item = (&'a String, &'a Document);
+    type IntoIter = Iter<'a, String, Document>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut DocumentMap {
+    type Item = (&'a String, &'a mut Document);
+    type IntoIter = IterMut<'a, String, Document>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
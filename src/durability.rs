@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// How far written content should be pushed towards the disk before a write operation on a [`Document`](crate::Document) returns.
+///
+/// *Flush*: hand the content over to the operating system. Fast, but recent writes may be lost if the computer crashes or loses power.
+///
+/// *SyncData*: also wait until the content of the file has reached the disk.
+///
+/// *SyncAll*: also wait until the content and the metadata of the file (such as its size and modification time) have reached the disk.
+///
+/// Set it with [`.durability(...)`](crate::document::ResultDocumentExt::durability) or [`Document::set_durability`](crate::Document::set_durability).
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Default, Serialize, Deserialize)]
+pub enum Durability {
+    #[default]
+    Flush,
+    SyncData,
+    SyncAll,
+}
@@ -0,0 +1,408 @@
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use crate::{Backend, FileHandle, LocalDisk, LockKind, Metadata, Mode};
+
+/// An operation of a [`Backend`](Backend) or of a [`FileHandle`](FileHandle) it opened, which a [`Fault`](Fault) can make fail.
+///
+/// *Exists*: checking whether a path exists. A fault makes the path look missing instead of failing.
+///
+/// *SetPermissions*: both [`set_readonly`](Backend::set_readonly) and [`set_mode`](Backend::set_mode).
+///
+/// *Read*, *Write*, *Sync*: reading, writing, and waiting for a file opened with *Open* or *CreateNew* to reach the disk.
+///
+/// *Lock*: taking an advisory lock on an opened file, with or without waiting. See [`FileHandle::lock`](FileHandle::lock).
+///
+/// Every other operation is the [`Backend`](Backend) method of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Open,
+    CreateNew,
+    CreateDirAll,
+    Exists,
+    Metadata,
+    Rename,
+    RemoveFile,
+    RemoveDir,
+    ReadDir,
+    SetTimes,
+    SetPermissions,
+    Copy,
+    SyncFolder,
+    Read,
+    Write,
+    Sync,
+    Lock,
+}
+
+/// A scripted failure of one [`Operation`](Operation), injected by [`Faulty`](Faulty).
+///
+/// *path*: only fail the operation on paths which end with this path, e.g. "notes.txt" or a full path. Any path by default.
+/// A rename or copy matches if either of its paths does.
+///
+/// *nth*: only fail the nth matching call, counting from 1. Every matching call by default.
+///
+/// *error*: the [`ErrorKind`](std::io::ErrorKind) of the injected error, e.g. `StorageFull` or `PermissionDenied`. `Other` by default.
+///
+/// *partial*: for *Write*, write this many bytes before failing, like a disk which fills up halfway through a write.
+///
+/// *crash*: after failing, make every following operation of the Faulty backend fail too, like a process which crashed,
+/// until [`recover`](Faulty::recover) is called. Nothing is cleaned up, so half-written temporary files are left behind.
+///
+/// ```ignore
+/// // The third write to notes.txt fails because the disk is full
+/// Fault::new(Operation::Write).path("notes.txt").nth(3).error(io::ErrorKind::StorageFull)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    operation: Operation,
+    path: Option<PathBuf>,
+    nth: Option<usize>,
+    error: io::ErrorKind,
+    partial: Option<usize>,
+    crash: bool,
+}
+
+impl Fault {
+    /// A fault which fails every call of `operation`.
+    pub fn new(operation: Operation) -> Self {
+        Self {
+            operation,
+            path: None,
+            nth: None,
+            error: io::ErrorKind::Other,
+            partial: None,
+            crash: false,
+        }
+    }
+
+    /// Only fail the operation on paths which end with `path`.
+    pub fn path(mut self, path: impl AsRef<Path>) -> Self {
+        self.path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Only fail the nth matching call, counting from 1.
+    pub fn nth(mut self, nth: usize) -> Self {
+        self.nth = Some(nth);
+        self
+    }
+
+    /// Fail with an error of this kind.
+    pub fn error(mut self, error: io::ErrorKind) -> Self {
+        self.error = error;
+        self
+    }
+
+    /// For *Write*, write this many bytes before failing.
+    pub fn partial(mut self, bytes: usize) -> Self {
+        self.partial = Some(bytes);
+        self
+    }
+
+    /// Make every following operation fail too, until [`recover`](Faulty::recover) is called.
+    pub fn crash(mut self) -> Self {
+        self.crash = true;
+        self
+    }
+
+    fn matches(&self, operation: Operation, paths: &[&Path]) -> bool {
+        self.operation == operation
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|fault_path| paths.iter().any(|path| path.ends_with(fault_path)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct FaultState {
+    faults: Vec<(Fault, usize)>,
+    crashed: bool,
+}
+
+/// A [`Backend`](Backend) which wraps another Backend ([`LocalDisk`](LocalDisk) by default) and makes its operations fail as scripted by [`Fault`](Fault)s,
+/// to test how code copes with full disks, missing permissions, races with other processes and crashes.
+///
+/// Clones share the same faults, so a test can keep a clone to add faults or [`recover`](Faulty::recover) after entering it.
+///
+/// ```ignore
+/// let faulty = Faulty::new(LocalDisk)
+///     .fail(Fault::new(Operation::Write).path("notes.txt").nth(3).error(io::ErrorKind::StorageFull))
+///     .fail(Fault::new(Operation::Open).path("secret.txt").error(io::ErrorKind::PermissionDenied));
+/// let _backend = faulty.clone().enter();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Faulty {
+    inner: Arc<dyn Backend>,
+    state: Arc<Mutex<FaultState>>,
+}
+
+impl Default for Faulty {
+    fn default() -> Self {
+        Self::new(LocalDisk)
+    }
+}
+
+impl Faulty {
+    /// A Faulty backend around `inner`, without faults.
+    pub fn new(inner: impl Backend + 'static) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            state: Arc::new(Mutex::new(FaultState::default())),
+        }
+    }
+
+    /// Add `fault` to the faults of this backend.
+    pub fn fail(self, fault: Fault) -> Self {
+        self.state().faults.push((fault, 0));
+        self
+    }
+
+    /// Remove every fault of this backend.
+    pub fn clear(&self) {
+        self.state().faults.clear();
+    }
+
+    /// Whether a [`crash`](Fault::crash) fault has happened and [`recover`](Faulty::recover) has not been called since.
+    pub fn crashed(&self) -> bool {
+        self.state().crashed
+    }
+
+    /// Let operations succeed again after a [`crash`](Fault::crash) fault.
+    pub fn recover(&self) {
+        self.state().crashed = false;
+    }
+
+    fn state(&self) -> MutexGuard<'_, FaultState> {
+        lock(&self.state)
+    }
+
+    /// Count the call of `operation` on `paths`, and return the fault to inject if any.
+    fn check(&self, operation: Operation, paths: &[&Path]) -> Result<(), InjectedFault> {
+        check(&self.state, operation, paths)
+    }
+
+    fn file(&self, file: Box<dyn FileHandle>, path: &Path) -> Box<dyn FileHandle> {
+        Box::new(FaultyFile {
+            inner: file,
+            path: path.to_path_buf(),
+            state: self.state.clone(),
+        })
+    }
+}
+
+fn lock(state: &Mutex<FaultState>) -> MutexGuard<'_, FaultState> {
+    state.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// The fault injected into one call: the error to return, and for writes how many bytes to write first.
+struct InjectedFault {
+    error: io::Error,
+    partial: Option<usize>,
+}
+
+impl From<InjectedFault> for io::Error {
+    fn from(fault: InjectedFault) -> Self {
+        fault.error
+    }
+}
+
+fn check(
+    state: &Mutex<FaultState>,
+    operation: Operation,
+    paths: &[&Path],
+) -> Result<(), InjectedFault> {
+    let path = paths.first().map(|path| path.display().to_string());
+    let path = path.unwrap_or_default();
+    let mut state = lock(state);
+    if state.crashed {
+        return Err(InjectedFault {
+            error: io::Error::other(format!("crashed before {operation:?} {path}")),
+            partial: None,
+        });
+    }
+    let mut injected = None;
+    for (fault, calls) in state.faults.iter_mut() {
+        if !fault.matches(operation, paths) {
+            continue;
+        }
+        *calls += 1;
+        if injected.is_none() && fault.nth.is_none_or(|nth| nth == *calls) {
+            injected = Some(fault.clone());
+        }
+    }
+    match injected {
+        Some(fault) => {
+            state.crashed |= fault.crash;
+            Err(InjectedFault {
+                error: io::Error::new(
+                    fault.error,
+                    format!("injected fault in {operation:?} {path}"),
+                ),
+                partial: fault.partial,
+            })
+        }
+        None => Ok(()),
+    }
+}
+
+impl Backend for Faulty {
+    fn open(&self, path: &Path, mode: Mode) -> io::Result<Box<dyn FileHandle>> {
+        self.check(Operation::Open, &[path])?;
+        Ok(self.file(self.inner.open(path, mode)?, path))
+    }
+
+    fn create_new(&self, path: &Path) -> io::Result<Box<dyn FileHandle>> {
+        self.check(Operation::CreateNew, &[path])?;
+        Ok(self.file(self.inner.create_new(path)?, path))
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        self.check(Operation::CreateDirAll, &[path])?;
+        self.inner.create_dir_all(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.check(Operation::Exists, &[path]).is_ok() && self.inner.exists(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.check(Operation::Metadata, &[path])?;
+        self.inner.metadata(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        self.check(Operation::Rename, &[from, to])?;
+        self.inner.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.check(Operation::RemoveFile, &[path])?;
+        self.inner.remove_file(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.check(Operation::RemoveDir, &[path])?;
+        self.inner.remove_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.check(Operation::ReadDir, &[path])?;
+        self.inner.read_dir(path)
+    }
+
+    fn set_times(
+        &self,
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        self.check(Operation::SetTimes, &[path])?;
+        self.inner.set_times(path, accessed, modified)
+    }
+
+    fn set_readonly(&self, path: &Path, readonly: bool) -> io::Result<()> {
+        self.check(Operation::SetPermissions, &[path])?;
+        self.inner.set_readonly(path, readonly)
+    }
+
+    fn set_mode(&self, path: &Path, mode: u32) -> io::Result<()> {
+        self.check(Operation::SetPermissions, &[path])?;
+        self.inner.set_mode(path, mode)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<u64> {
+        self.check(Operation::Copy, &[from, to])?;
+        self.inner.copy(from, to)
+    }
+
+    fn sync_folder(&self, path: &Path) -> io::Result<()> {
+        self.check(Operation::SyncFolder, &[path])?;
+        self.inner.sync_folder(path)
+    }
+
+    fn std_file(&self, path: &Path, mode: Mode) -> io::Result<File> {
+        self.check(Operation::Open, &[path])?;
+        self.inner.std_file(path, mode)
+    }
+}
+
+/// A file opened through a [`Faulty`](Faulty) backend, whose reads, writes and syncs can fail too.
+struct FaultyFile {
+    inner: Box<dyn FileHandle>,
+    path: PathBuf,
+    state: Arc<Mutex<FaultState>>,
+}
+
+impl Debug for FaultyFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaultyFile")
+            .field("inner", &self.inner)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl FaultyFile {
+    fn check(&self, operation: Operation) -> Result<(), InjectedFault> {
+        check(&self.state, operation, &[&self.path])
+    }
+}
+
+impl Read for FaultyFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check(Operation::Read)?;
+        self.inner.read(buf)
+    }
+}
+
+impl Write for FaultyFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Err(fault) = self.check(Operation::Write) {
+            if let Some(bytes) = fault.partial {
+                self.inner.write_all(&buf[..bytes.min(buf.len())])?;
+            }
+            Err(fault)?
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for FaultyFile {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(position)
+    }
+}
+
+impl FileHandle for FaultyFile {
+    fn sync_data(&mut self) -> io::Result<()> {
+        self.check(Operation::Sync)?;
+        self.inner.sync_data()
+    }
+    fn sync_all(&mut self) -> io::Result<()> {
+        self.check(Operation::Sync)?;
+        self.inner.sync_all()
+    }
+    fn lock(&mut self, kind: LockKind) -> io::Result<()> {
+        self.check(Operation::Lock)?;
+        self.inner.lock(kind)
+    }
+    fn try_lock(&mut self, kind: LockKind) -> io::Result<bool> {
+        self.check(Operation::Lock)?;
+        self.inner.try_lock(kind)
+    }
+    fn unlock(&mut self) -> io::Result<()> {
+        self.inner.unlock()
+    }
+}
@@ -1,11 +1,16 @@
-use std::{fmt::Debug, path::PathBuf};
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+
+use crate::{backend::current_backend, DocumentError, Metadata};
 
 /// Common capabilities supported by [`Document`](Document)s, [`Folder`](Folder)s and [`PathBuf`](std::path::PathBuf)s
 ///
 /// Note: this trait is object-safe, which means it can be used as variable, function parameter and function return
 /// types.
 ///
-/// ```
+/// ```ignore
 /// fn test2() {
 ///     let a: &[&dyn FileSystemEntity] = &[
 ///         &Document::at(User(Pictures([])), "pic", Create::No),
@@ -27,6 +32,12 @@ pub trait FileSystemEntity: Debug {
     fn name(&self) -> String;
     /// Whether this FileSystemEntity exists.
     fn exists(&self) -> bool;
+    /// The size, timestamps, permissions and kind of this FileSystemEntity. See [`Metadata`](Metadata).
+    ///
+    /// Returns an error if the metadata could not be read, e.g. because this FileSystemEntity does not exist.
+    fn metadata(&self) -> Result<Metadata, DocumentError> {
+        Metadata::of(Path::new(&self.path()))
+    }
 }
 
 impl FileSystemEntity for PathBuf {
@@ -41,9 +52,6 @@ impl FileSystemEntity for PathBuf {
         self.display().to_string()
     }
     fn exists(&self) -> bool {
-        match self.try_exists() {
-            Ok(value) if value => true,
-            _ => false,
-        }
+        current_backend().exists(self)
     }
 }
@@ -1,14 +1,12 @@
-use std::{
-    fmt::Display,
-    path::{Path, PathBuf},
-    usize,
-};
+use std::{fmt::Display, path::PathBuf};
 
-use crate::{DocumentError, FileSystemEntity};
+use crate::{
+    backend::current_backend, AppId, DocumentError, FileSystemEntity, FolderPath, KnownFolder, Walk,
+};
 
 /// A type that represents well-known folders that are likely to exist on most devices.
 ///
-/// See also [`User`](User) and [`Project`](Project)
+/// See also [`User`](User) and [`Project`](Project), and [`FolderPath`](FolderPath) for a folder location which can be built at runtime.
 ///
 /// e.g.
 ///
@@ -21,110 +19,80 @@ pub enum Folder<'a, const N: usize> {
     Project((Project<'a, N>, &'a str, &'a str, &'a str)),
 }
 
-fn join_all(path: &Path, subdirs: &[&str]) -> PathBuf {
-    let mut pathbuf = path.to_path_buf();
-    for subdir in subdirs {
-        pathbuf.push(subdir);
-    }
-    pathbuf
-}
-
 impl<'a, const N: usize> Folder<'a, N> {
-    pub(crate) fn into_pathbuf_result(
-        &self,
-        filename: impl Display,
-    ) -> Result<PathBuf, DocumentError> {
+    pub(crate) fn into_parts(self) -> (KnownFolder, [&'a str; N]) {
         match self {
             Folder::User(subdir) => match subdir {
-                User::Pictures(subdirs) => {
-                    if let Some(dir) = directories::UserDirs::new() {
-                        if let Some(path) = dir.picture_dir() {
-                            let mut pathbuf = join_all(path, subdirs);
-                            pathbuf = pathbuf.join(filename.to_string());
-                            Ok(pathbuf)
-                        } else {
-                            Err(DocumentError::PicturesDirNotFound)?
-                        }
-                    } else {
-                        Err(DocumentError::UserDirsNotFound)?
-                    }
-                }
-                User::Videos(subdirs) => {
-                    if let Some(dir) = directories::UserDirs::new() {
-                        if let Some(path) = dir.video_dir() {
-                            let mut pathbuf = join_all(path, subdirs);
-                            pathbuf = pathbuf.join(filename.to_string());
-                            Ok(pathbuf)
-                        } else {
-                            Err(DocumentError::VideosDirNotFound)?
-                        }
-                    } else {
-                        Err(DocumentError::UserDirsNotFound)?
-                    }
-                }
-                User::Downloads(subdirs) => {
-                    if let Some(dir) = directories::UserDirs::new() {
-                        if let Some(path) = dir.download_dir() {
-                            let mut pathbuf = join_all(path, subdirs);
-                            pathbuf = pathbuf.join(filename.to_string());
-                            Ok(pathbuf)
-                        } else {
-                            Err(DocumentError::DownloadsDirNotFound)?
-                        }
-                    } else {
-                        Err(DocumentError::UserDirsNotFound)?
-                    }
-                }
-                User::Documents(subdirs) => {
-                    if let Some(dir) = directories::UserDirs::new() {
-                        if let Some(path) = dir.document_dir() {
-                            let mut pathbuf = join_all(path, subdirs);
-                            pathbuf = pathbuf.join(filename.to_string());
-                            Ok(pathbuf)
-                        } else {
-                            Err(DocumentError::DocumentsDirNotFound)?
-                        }
-                    } else {
-                        Err(DocumentError::UserDirsNotFound)?
-                    }
-                }
-                User::Home(subdirs) => {
-                    if let Some(dir) = directories::UserDirs::new() {
-                        let path = dir.home_dir();
-                        let mut pathbuf = join_all(path, subdirs);
-                        pathbuf = pathbuf.join(filename.to_string());
-                        Ok(pathbuf)
-                    } else {
-                        Err(DocumentError::UserDirsNotFound)?
-                    }
-                }
+                User::Documents(subdirs) => (KnownFolder::Documents, subdirs),
+                User::Pictures(subdirs) => (KnownFolder::Pictures, subdirs),
+                User::Videos(subdirs) => (KnownFolder::Videos, subdirs),
+                User::Downloads(subdirs) => (KnownFolder::Downloads, subdirs),
+                User::Home(subdirs) => (KnownFolder::Home, subdirs),
+                User::Desktop(subdirs) => (KnownFolder::Desktop, subdirs),
+                User::Music(subdirs) => (KnownFolder::Music, subdirs),
+                User::Public(subdirs) => (KnownFolder::Public, subdirs),
+                User::Templates(subdirs) => (KnownFolder::Templates, subdirs),
+                User::Fonts(subdirs) => (KnownFolder::Fonts, subdirs),
             },
-            Folder::Project((subdir, qualifier, organization, application)) => match subdir {
-                Project::Data(subdirs) => {
-                    if let Some(dir) =
-                        directories::ProjectDirs::from(qualifier, organization, application)
-                    {
-                        let mut pathbuf = join_all(dir.data_dir(), subdirs);
-                        pathbuf = pathbuf.join(filename.to_string());
-                        Ok(pathbuf)
-                    } else {
-                        Err(DocumentError::ProjectDirsNotFound)?
-                    }
+            Folder::Project((subdir, qualifier, organization, application)) => {
+                let app_id = AppId::new(qualifier, organization, application);
+                match subdir {
+                    Project::Config(subdirs) => (KnownFolder::Config(app_id), subdirs),
+                    Project::Data(subdirs) => (KnownFolder::Data(app_id), subdirs),
+                    Project::DataLocal(subdirs) => (KnownFolder::DataLocal(app_id), subdirs),
+                    Project::Cache(subdirs) => (KnownFolder::Cache(app_id), subdirs),
+                    Project::State(subdirs) => (KnownFolder::State(app_id), subdirs),
+                    Project::Runtime(subdirs) => (KnownFolder::Runtime(app_id), subdirs),
+                    Project::Preference(subdirs) => (KnownFolder::Preference(app_id), subdirs),
                 }
-                Project::Config(subdirs) => {
-                    if let Some(dir) =
-                        directories::ProjectDirs::from(qualifier, organization, application)
-                    {
-                        let mut pathbuf = join_all(dir.config_dir(), subdirs);
-                        pathbuf = pathbuf.join(filename.to_string());
-                        Ok(pathbuf)
-                    } else {
-                        Err(DocumentError::ProjectDirsNotFound)?
-                    }
-                }
-            },
+            }
         }
     }
+
+    /// Returns an iterator over the files in this folder, as [`Document`](crate::Document)s which can be given to [`with`](crate::with) as-is.
+    ///
+    /// Files whose names start with "." are skipped. Same as `.walk().max_depth(1)`.
+    ///
+    /// ```ignore
+    /// with(User(Downloads(["Invoices"])).documents(), |d| {
+    ///     for (alias, document) in d {
+    ///         println!("{alias} at {}", document.path());
+    ///     }
+    /// });
+    /// ```
+    pub fn documents(self) -> Walk {
+        FolderPath::from(self).documents()
+    }
+
+    /// Returns an iterator over the files in this folder and all of its subfolders. See [`Walk`](Walk) for its options.
+    pub fn walk(self) -> Walk {
+        FolderPath::from(self).walk()
+    }
+
+    /// The folders directly in this folder as [`FolderPath`](FolderPath)s, in alphabetical order. Folders whose names start with "." are skipped.
+    ///
+    /// Note: this is what was asked for as `subfolders()`. It is named `child_folders` because [`FolderPath::subfolders`](FolderPath::subfolders)
+    /// already lists the subfolders a FolderPath is made of.
+    ///
+    /// Returns an error if this folder could not be read.
+    pub fn child_folders(self) -> Result<Vec<FolderPath>, DocumentError> {
+        FolderPath::from(self).child_folders()
+    }
+
+    /// Move this folder and everything in it to the trash. See [`TrashItem`](crate::TrashItem).
+    ///
+    /// Returns an error if the folder could not be moved to the trash.
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    pub fn trash(self) -> Result<crate::TrashItem, DocumentError> {
+        FolderPath::from(self).trash()
+    }
+
+    pub(crate) fn into_pathbuf_result(
+        self,
+        filename: impl Display,
+    ) -> Result<PathBuf, DocumentError> {
+        FolderPath::from(self).to_pathbuf_result(filename)
+    }
 }
 
 /// A type that represents well-known user folders.
@@ -136,10 +104,18 @@ impl<'a, const N: usize> Folder<'a, N> {
 ///     Videos([&'a str; N]),
 ///     Downloads([&'a str; N]),
 ///     Home([&'a str; N]),
+///     Desktop([&'a str; N]),
+///     Music([&'a str; N]),
+///     Public([&'a str; N]),
+///     Templates([&'a str; N]),
+///     Fonts([&'a str; N]),
 /// }
 /// ```
 ///
 /// Put subdirectories under the respective folders like so: `Pictures(["Screenshots", "July", "14"])`.
+///
+/// Note: not every folder exists on every operating system, e.g. Windows has no Fonts folder for each user.
+/// Using a folder which does not exist returns an error such as [`DocumentError::FontsDirNotFound`](crate::DocumentError::FontsDirNotFound).
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum User<'a, const N: usize> {
     Documents([&'a str; N]),
@@ -147,6 +123,11 @@ pub enum User<'a, const N: usize> {
     Videos([&'a str; N]),
     Downloads([&'a str; N]),
     Home([&'a str; N]),
+    Desktop([&'a str; N]),
+    Music([&'a str; N]),
+    Public([&'a str; N]),
+    Templates([&'a str; N]),
+    Fonts([&'a str; N]),
 }
 
 /// A type that represents the application's project folder. An isolated folder is usually provided per app per user by the operating system for apps to put internal files.
@@ -161,10 +142,27 @@ pub enum User<'a, const N: usize> {
 /// *Config*: place configuration files here, such as app settings.
 ///
 /// *Data*: place data files here, such as a web browser's adblock filters.
+///
+/// *DataLocal*: place data files here which should stay on this device, i.e. which should not roam to the user's other devices. Same as *Data* except on Windows.
+///
+/// *Cache*: place files here which can be recreated if they are deleted, such as downloaded thumbnails. Backup tools usually skip this folder.
+///
+/// *State*: place files here which should survive restarts but are not important enough for *Data*, such as logs and recently opened files.
+/// Only exists on Linux --- returns [`DocumentError::StateDirNotFound`](crate::DocumentError::StateDirNotFound) elsewhere.
+///
+/// *Runtime*: place files here which are only useful while the app runs, such as sockets. Only exists on Linux when `$XDG_RUNTIME_DIR` is set ---
+/// returns [`DocumentError::RuntimeDirNotFound`](crate::DocumentError::RuntimeDirNotFound) otherwise.
+///
+/// *Preference*: place preference files here. Same as *Config* except on macOS, where it is the Preferences folder.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Project<'a, const N: usize> {
     Config([&'a str; N]),
     Data([&'a str; N]),
+    DataLocal([&'a str; N]),
+    Cache([&'a str; N]),
+    State([&'a str; N]),
+    Runtime([&'a str; N]),
+    Preference([&'a str; N]),
 }
 
 impl<'a, const N: usize> Project<'a, N> {
@@ -184,7 +182,7 @@ impl<'a, const N: usize> Project<'a, N> {
 
 impl<'a, const N: usize> FileSystemEntity for Folder<'a, N> {
     fn exists(&self) -> bool {
-        self.into_pathbuf_result("").unwrap_or_default().exists()
+        current_backend().exists(&self.into_pathbuf_result("").unwrap_or_default())
     }
     fn name(&self) -> String {
         self.into_pathbuf_result("")
@@ -0,0 +1,231 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    backend::current_backend, roots::with_current_roots, DocumentError, EntryKind,
+    FileSystemEntity, Folder, Walk,
+};
+
+/// A well-known folder which a [`FolderPath`](FolderPath) starts from.
+///
+/// The user folders are the same as in [`User`](crate::folder::User), the project folders are the same as in [`Project`](crate::folder::Project)
+/// and need the [`AppId`](AppId) of your app.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KnownFolder {
+    Documents,
+    Pictures,
+    Videos,
+    Downloads,
+    Home,
+    Desktop,
+    Music,
+    Public,
+    Templates,
+    Fonts,
+    Config(AppId),
+    Data(AppId),
+    DataLocal(AppId),
+    Cache(AppId),
+    State(AppId),
+    Runtime(AppId),
+    Preference(AppId),
+}
+
+/// The app ID which identifies the project folders of your app. See [`Project::with_id`](crate::folder::Project::with_id).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AppId {
+    pub qualifier: String,
+    pub organization: String,
+    pub application: String,
+}
+
+impl AppId {
+    /// The app ID "com.example.App" has the qualifier "com", the organization "example" and the application "App".
+    pub fn new(
+        qualifier: impl Display,
+        organization: impl Display,
+        application: impl Display,
+    ) -> Self {
+        Self {
+            qualifier: qualifier.to_string(),
+            organization: organization.to_string(),
+            application: application.to_string(),
+        }
+    }
+
+    pub(crate) fn project_dirs(&self) -> Result<ProjectDirs, DocumentError> {
+        match ProjectDirs::from(&self.qualifier, &self.organization, &self.application) {
+            Some(dirs) => Ok(dirs),
+            None => Err(DocumentError::ProjectDirsNotFound),
+        }
+    }
+}
+
+impl KnownFolder {
+    pub(crate) fn to_pathbuf_result(&self) -> Result<PathBuf, DocumentError> {
+        with_current_roots(|roots| roots.folder(self))
+    }
+}
+
+/// An owned location of a folder: a [`KnownFolder`](KnownFolder) and a list of subfolders under it.
+///
+/// Unlike [`Folder`](Folder), this type can be built at runtime (e.g. from a `Vec<String>`) and kept in structs without lifetimes.
+/// Any [`Folder`](Folder) can be converted into a FolderPath with `.into()` or `FolderPath::from(...)`, and both can be given to [`Document::at`](crate::Document::at).
+///
+/// ```ignore
+/// let mut invoices = FolderPath::from(User(Documents(["Invoices"])));
+/// invoices.push(year.to_string());
+/// let document = Document::at(invoices.join("March"), "invoice.pdf", Create::No);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FolderPath {
+    pub(crate) base: KnownFolder,
+    pub(crate) subfolders: Vec<String>,
+}
+
+fn join_all(path: &Path, subdirs: &[impl AsRef<Path>]) -> PathBuf {
+    let mut pathbuf = path.to_path_buf();
+    for subdir in subdirs {
+        pathbuf.push(subdir);
+    }
+    pathbuf
+}
+
+impl FolderPath {
+    /// The [`KnownFolder`](KnownFolder) itself, without subfolders.
+    pub fn new(base: KnownFolder) -> Self {
+        Self {
+            base,
+            subfolders: Vec::new(),
+        }
+    }
+
+    /// The [`KnownFolder`](KnownFolder) this FolderPath starts from.
+    pub fn base(&self) -> &KnownFolder {
+        &self.base
+    }
+
+    /// The subfolders under the [`KnownFolder`](KnownFolder), from the outermost to the innermost.
+    pub fn subfolders(&self) -> &[String] {
+        &self.subfolders
+    }
+
+    /// Add a subfolder to the end of this FolderPath.
+    pub fn push(&mut self, subfolder: impl Display) -> &mut Self {
+        self.subfolders.push(subfolder.to_string());
+        self
+    }
+
+    /// A new FolderPath with `subfolder` added to the end of this FolderPath.
+    pub fn join(&self, subfolder: impl Display) -> Self {
+        let mut folder_path = self.clone();
+        folder_path.push(subfolder);
+        folder_path
+    }
+
+    /// The FolderPath one subfolder up. Returns None if this FolderPath is the [`KnownFolder`](KnownFolder) itself.
+    pub fn parent(&self) -> Option<Self> {
+        let mut folder_path = self.clone();
+        folder_path.subfolders.pop()?;
+        Some(folder_path)
+    }
+
+    /// Returns an iterator over the files in this folder, as [`Document`](crate::Document)s which can be given to [`with`](crate::with) as-is.
+    ///
+    /// Files whose names start with "." are skipped. Same as `.walk().max_depth(1)`.
+    pub fn documents(&self) -> Walk {
+        self.walk().max_depth(1)
+    }
+
+    /// Returns an iterator over the files in this folder and all of its subfolders. See [`Walk`](Walk) for its options.
+    pub fn walk(&self) -> Walk {
+        Walk::new(self.to_pathbuf_result(""))
+    }
+
+    /// The folders directly in this folder, in alphabetical order. Folders whose names start with "." are skipped.
+    ///
+    /// Not to be confused with [`subfolders`](FolderPath::subfolders), which lists the subfolders this FolderPath is made of.
+    ///
+    /// Returns an error if this folder could not be read.
+    pub fn child_folders(&self) -> Result<Vec<FolderPath>, DocumentError> {
+        let pathbuf = self.to_pathbuf_result("")?;
+        let backend = current_backend();
+        let entries = match backend.read_dir(&pathbuf) {
+            Ok(entries) => entries,
+            Err(error) => Err(DocumentError::CouldNotReadFolder(pathbuf.path(), error))?,
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let name = entry.name();
+            let is_folder = backend
+                .metadata(&entry)
+                .is_ok_and(|metadata| metadata.kind == EntryKind::Folder);
+            if is_folder && !name.starts_with('.') {
+                names.push(name);
+            }
+        }
+        names.sort();
+        Ok(names.into_iter().map(|name| self.join(name)).collect())
+    }
+
+    /// Move this folder and everything in it to the trash. See [`TrashItem`](crate::TrashItem).
+    ///
+    /// Returns an error if the folder could not be moved to the trash.
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    pub fn trash(&self) -> Result<crate::TrashItem, DocumentError> {
+        crate::trash::trash(current_backend().as_ref(), &self.to_pathbuf_result("")?)
+    }
+
+    pub(crate) fn to_pathbuf_result(
+        &self,
+        filename: impl Display,
+    ) -> Result<PathBuf, DocumentError> {
+        let pathbuf = join_all(&self.base.to_pathbuf_result()?, &self.subfolders);
+        Ok(pathbuf.join(filename.to_string()))
+    }
+}
+
+impl From<KnownFolder> for FolderPath {
+    fn from(base: KnownFolder) -> Self {
+        Self::new(base)
+    }
+}
+
+impl<'a, const N: usize> From<Folder<'a, N>> for FolderPath {
+    fn from(folder: Folder<'a, N>) -> Self {
+        let (base, subfolders) = folder.into_parts();
+        Self {
+            base,
+            subfolders: subfolders
+                .iter()
+                .map(|subfolder| subfolder.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl FileSystemEntity for FolderPath {
+    fn exists(&self) -> bool {
+        current_backend().exists(&self.to_pathbuf_result("").unwrap_or_default())
+    }
+    fn name(&self) -> String {
+        self.to_pathbuf_result("")
+            .unwrap_or_default()
+            .file_name()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap_or_default()
+            .to_string()
+    }
+    fn path(&self) -> String {
+        self.to_pathbuf_result("")
+            .unwrap_or_default()
+            .display()
+            .to_string()
+    }
+}
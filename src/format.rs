@@ -0,0 +1,184 @@
+use std::{error::Error, fmt::Display};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::DocumentError;
+
+/// A file format which [`Document::load`](crate::Document::load) and [`Document::save`](crate::Document::save) can convert values from and to.
+///
+/// Each format is only available when its cargo feature is enabled, otherwise loading and saving returns
+/// [`DocumentError::FormatNotEnabled`](crate::DocumentError::FormatNotEnabled):
+///
+/// *Json*: the `json` feature, for files ending with ".json".
+///
+/// *Toml*: the `toml` feature, for files ending with ".toml".
+///
+/// *Yaml*: the `yaml` feature, for files ending with ".yaml" or ".yml".
+///
+/// *Ron*: the `ron` feature, for files ending with ".ron".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+    Ron,
+}
+
+impl Format {
+    /// The format of files with the extension `extension`, with or without the leading ".", e.g. "toml" or ".toml".
+    /// Returns None if no format uses this extension.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.trim_start_matches('.').to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "ron" => Some(Self::Ron),
+            _ => None,
+        }
+    }
+
+    /// The cargo feature which enables this format.
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+            Self::Ron => "ron",
+        }
+    }
+
+    /// Convert `content`, the content of the file at `path`, into a `T`.
+    #[allow(unused_variables)]
+    pub(crate) fn deserialize<T: DeserializeOwned>(
+        self,
+        path: String,
+        content: &str,
+    ) -> Result<T, DocumentError> {
+        let result: Result<T, ParseError> = match self {
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::from_str(content).map_err(|error| {
+                // serde_json reports line 0 for errors which are not about a position.
+                let position = (error.line() > 0).then(|| (error.line(), error.column()));
+                ParseError::new(self, position, &error)
+            }),
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::from_str(content).map_err(|error| {
+                let position = error
+                    .span()
+                    .map(|span| line_and_column(content, span.start));
+                ParseError::new(self, position, error.message())
+            }),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::from_str(content).map_err(|error| {
+                let position = error
+                    .location()
+                    .map(|location| (location.line(), location.column()));
+                ParseError::new(self, position, &error)
+            }),
+            #[cfg(feature = "ron")]
+            Self::Ron => ron::from_str(content).map_err(|error| {
+                let position = Some((error.position.line, error.position.col));
+                ParseError::new(self, position, &error.code)
+            }),
+            #[allow(unreachable_patterns)]
+            _ => Err(DocumentError::FormatNotEnabled(path.clone(), self))?,
+        };
+        result.map_err(|error| DocumentError::CouldNotParseFile(path, error))
+    }
+
+    /// Convert `value` into the content of the file at `path`.
+    #[allow(unused_variables)]
+    pub(crate) fn serialize<T: Serialize>(
+        self,
+        path: String,
+        value: &T,
+    ) -> Result<String, DocumentError> {
+        let result: Result<String, String> = match self {
+            #[cfg(feature = "json")]
+            Self::Json => serde_json::to_string_pretty(value)
+                .map(|content| content + "\n")
+                .map_err(|error| error.to_string()),
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::to_string_pretty(value).map_err(|error| error.to_string()),
+            #[cfg(feature = "yaml")]
+            Self::Yaml => serde_yaml::to_string(value).map_err(|error| error.to_string()),
+            #[cfg(feature = "ron")]
+            Self::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                .map(|content| content + "\n")
+                .map_err(|error| error.to_string()),
+            #[allow(unreachable_patterns)]
+            _ => Err(DocumentError::FormatNotEnabled(path.clone(), self))?,
+        };
+        result.map_err(|reason| DocumentError::CouldNotSerializeValue(path, reason))
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+            Self::Ron => "RON",
+        };
+        f.pad(name)
+    }
+}
+
+/// Why the content of a file could not be parsed, carried by [`DocumentError::CouldNotParseFile`](crate::DocumentError::CouldNotParseFile).
+///
+/// *format*: the [`Format`](Format) the content was parsed as.
+///
+/// *line*, *column*: where in the content parsing failed, both starting from 1. None if the parser did not report a position.
+///
+/// *message*: what went wrong, as described by the parser.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ParseError {
+    pub format: Format,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl ParseError {
+    #[allow(dead_code)]
+    pub(crate) fn new(
+        format: Format,
+        position: Option<(usize, usize)>,
+        message: impl Display,
+    ) -> Self {
+        Self {
+            format,
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "invalid {} at line {line}, column {column}: {}",
+                self.format, self.message
+            ),
+            _ => write!(f, "invalid {}: {}", self.format, self.message),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// The line and column (both starting from 1) of the byte at `offset` in `content`.
+#[cfg(feature = "toml")]
+fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
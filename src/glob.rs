@@ -0,0 +1,124 @@
+/// Whether `path` (with folders separated by "/") matches the glob `pattern`.
+///
+/// `*` matches any characters within a folder or file name, `?` matches one character,
+/// `[abc]`, `[a-z]` and `[!abc]` match one character out of (or not out of) a set,
+/// and a `**` segment matches any number of folders, including none.
+/// Hidden files and folders, whose names start with ".", are only matched by segments which start with "." too.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern_segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path_segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern_segments, &path_segments)
+}
+
+/// The number of folder levels `pattern` can match, or None if it contains `**` and can match any number.
+pub(crate) fn glob_depth(pattern: &str) -> Option<usize> {
+    let segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.contains(&"**") {
+        None
+    } else {
+        Some(segments.len())
+    }
+}
+
+/// Whether the `pattern` segments match the `path` segments, going through the pattern one segment at a time.
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    // Whether the pattern segments gone through so far match the first `index` path segments.
+    let mut matched = vec![false; path.len() + 1];
+    matched[0] = true;
+    for segment in pattern {
+        let mut next = vec![false; path.len() + 1];
+        for index in 0..=path.len() {
+            next[index] = if *segment == "**" {
+                // Any number of folders, as long as they are not hidden.
+                matched[index]
+                    || (index > 0 && next[index - 1] && !path[index - 1].starts_with('.'))
+            } else {
+                index > 0 && matched[index - 1] && match_name(segment, path[index - 1])
+            };
+        }
+        matched = next;
+    }
+    matched[path.len()]
+}
+
+/// Whether the `pattern` segment matches the file or folder `name`. Hidden names, which start with ".", only match segments which start with "." too.
+fn match_name(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut pattern_index, mut name_index) = (0, 0);
+    // Where to continue after the last "*" if the rest does not match: the pattern after it, and the next character for it to match.
+    let mut last_star = None;
+    while name_index < name.len() {
+        if pattern.get(pattern_index) == Some(&'*') {
+            pattern_index += 1;
+            last_star = Some((pattern_index, name_index));
+            continue;
+        }
+        if let Some(len) = match_character(&pattern[pattern_index..], name[name_index]) {
+            pattern_index += len;
+            name_index += 1;
+            continue;
+        }
+        match last_star {
+            Some((after_star, star_end)) => {
+                pattern_index = after_star;
+                name_index = star_end + 1;
+                last_star = Some((after_star, star_end + 1));
+            }
+            None => return false,
+        }
+    }
+    pattern[pattern_index..]
+        .iter()
+        .all(|character| *character == '*')
+}
+
+/// Whether the start of `pattern`, which is not "*", matches `character`. Returns how many characters of the pattern it took.
+fn match_character(pattern: &[char], character: char) -> Option<usize> {
+    match pattern.split_first() {
+        None => None,
+        Some(('?', _)) => Some(1),
+        Some(('[', rest)) => match parse_class(rest) {
+            Some((matches, after_class)) => {
+                matches(character).then_some(pattern.len() - after_class.len())
+            }
+            // An unclosed "[" is matched literally.
+            None => (character == '[').then_some(1),
+        },
+        Some((literal, _)) => (*literal == character).then_some(1),
+    }
+}
+
+/// Parse a character class after its opening "[". Returns the test for a character and the pattern after the closing "]".
+#[allow(clippy::type_complexity)]
+fn parse_class(pattern: &[char]) -> Option<(Box<dyn Fn(char) -> bool + '_>, &[char])> {
+    let (negated, pattern) = match pattern.split_first() {
+        Some(('!', rest)) | Some(('^', rest)) => (true, rest),
+        _ => (false, pattern),
+    };
+    // A "]" right after the opening "[" is part of the set.
+    let close = pattern
+        .iter()
+        .skip(1)
+        .position(|character| *character == ']')?
+        + 1;
+    let set = &pattern[..close];
+    let matches = move |character: char| {
+        let mut index = 0;
+        let mut found = false;
+        while index < set.len() {
+            if index + 2 < set.len() && set[index + 1] == '-' {
+                found |= set[index] <= character && character <= set[index + 2];
+                index += 3;
+            } else {
+                found |= set[index] == character;
+                index += 1;
+            }
+        }
+        found != negated
+    };
+    Some((Box::new(matches), &pattern[close + 1..]))
+}
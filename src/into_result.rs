@@ -1,11 +1,11 @@
 use std::{error::Error, fmt::Display};
 
-/// Maps types implementing this trait to `Result<(), Box<dyn Error>>`.
+/// Maps types implementing this trait to `Result<Output, Box<dyn Error>>`.
 ///
 /// Note: this trait is *not* object-safe, which means it cannot be used as the type of a variable.
 /// However, `impl IntoResult` can be used for function parameters and return types.
 ///
-/// ```
+/// ```ignore
 /// fn may_fail_on_paper() -> impl IntoResult {
 ///     doesnt_actually_fail();
 ///     // Returns (), acceptable
@@ -25,15 +25,18 @@ use std::{error::Error, fmt::Display};
 /// ```
 ///
 /// Implemented for [`()`](https://doc.rust-lang.org/std/primitive.unit.html),
-/// [`Option<T>`](std::option::Option) and [`Result<(), Box<dyn Error>>`](std::result::Result) out-of-the-box
+/// [`Option<T>`](std::option::Option) and [`Result<T, Box<dyn Error>>`](std::result::Result) out-of-the-box
 pub trait IntoResult {
-    fn into_result(self) -> Result<(), Box<dyn Error>>;
+    /// The value carried on success: `()` for `()`, and `T` for `Option<T>` and `Result<T, Box<dyn Error>>`.
+    type Output;
+    fn into_result(self) -> Result<Self::Output, Box<dyn Error>>;
 }
 
 impl IntoResult for () {
+    type Output = ();
     /// Implementation
     ///
-    /// ```
+    /// ```ignore
     /// fn into_result(self) -> Result<(), Box<dyn Error>> {
     ///     Ok(())
     /// }
@@ -44,40 +47,36 @@ impl IntoResult for () {
 }
 
 impl<T> IntoResult for Option<T> {
+    type Output = T;
     /// Implementation
     ///
-    /// ```
-    /// fn into_result(self) -> Result<(), Box<dyn Error>> {
+    /// ```ignore
+    /// fn into_result(self) -> Result<T, Box<dyn Error>> {
     ///     match self {
-    ///         Some(_) => Ok(()),
+    ///         Some(value) => Ok(value),
     ///         None => Err(Box::new(NoneError)),
     ///     }
     /// }
     /// ```
-    fn into_result(self) -> Result<(), Box<dyn Error>> {
+    fn into_result(self) -> Result<T, Box<dyn Error>> {
         match self {
-            Some(_) => Ok(()),
+            Some(value) => Ok(value),
             None => Err(Box::new(NoneError)),
         }
     }
 }
 
 impl<T> IntoResult for Result<T, Box<dyn Error>> {
+    type Output = T;
     /// Implementation
     ///
-    /// ```
-    /// fn into_result(self) -> Result<(), Box<dyn Error>> {
-    ///     match self {
-    ///         Ok(_) => Ok(()),
-    ///         Err(error) => Err(error),
-    ///     }
+    /// ```ignore
+    /// fn into_result(self) -> Result<T, Box<dyn Error>> {
+    ///     self
     /// }
     /// ```
-    fn into_result(self) -> Result<(), Box<dyn Error>> {
-        match self {
-            Ok(_) => Ok(()),
-            Err(error) => Err(error),
-        }
+    fn into_result(self) -> Result<T, Box<dyn Error>> {
+        self
     }
 }
 
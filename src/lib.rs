@@ -1,14 +1,22 @@
 use std::collections::HashMap;
-use std::error::Error;
+use std::path::PathBuf;
+
+use backend::{enter_backend, BackendRef};
+use document::{remove_created, rename_durably, sync_file, Staging};
+use roots::enter_roots;
 
 mod mode;
 pub use mode::Mode;
+mod access;
+pub use access::{Access, ReadOnly, ReadWrite};
 mod folder;
 pub use folder::Folder;
+mod folder_path;
+pub use folder_path::{AppId, FolderPath, KnownFolder};
 mod create;
 pub use create::Create;
 mod document_error;
-pub use document_error::DocumentError;
+pub use document_error::{DocumentError, DocumentErrorKind};
 mod document;
 pub use document::Document;
 mod filesystem_entity;
@@ -17,6 +25,49 @@ mod document_map;
 pub use document_map::DocumentMap;
 mod into_result;
 pub use into_result::IntoResult;
+mod chunks;
+pub use chunks::Chunks;
+mod durability;
+pub use durability::Durability;
+mod with_error;
+pub use with_error::WithError;
+mod with_options;
+pub use with_options::WithOptions;
+mod walk;
+pub use walk::{SortOrder, Walk};
+mod format;
+mod glob;
+pub use format::{Format, ParseError};
+#[cfg(feature = "async")]
+mod async_document;
+#[cfg(feature = "async")]
+pub use async_document::AsyncLines;
+mod backend;
+mod metadata;
+pub use backend::{
+    reset_backend, set_backend, Backend, BackendGuard, FileHandle, InMemory, LocalDisk,
+};
+mod faulty;
+pub use faulty::{Fault, Faulty, Operation};
+mod lock;
+pub use lock::{DocumentLock, LockKind};
+mod open_document;
+pub use open_document::OpenDocument;
+mod roots;
+pub use roots::{reset_roots, set_roots, Roots, RootsGuard, Sandbox, SystemRoots};
+mod search_path;
+#[cfg(feature = "settings")]
+pub use search_path::Merged;
+pub use search_path::SearchPath;
+#[cfg(feature = "settings")]
+mod settings;
+pub use metadata::{EntryKind, Metadata};
+#[cfg(feature = "settings")]
+pub use settings::{Schema, Settings, SCHEMA_VERSION_KEY};
+#[cfg(all(unix, not(target_vendor = "apple")))]
+mod trash;
+#[cfg(all(unix, not(target_vendor = "apple")))]
+pub use trash::TrashItem;
 
 /// A way to declare all of the [`Document`](Document)s in one place then access them in the `closure` through a [`DocumentMap`](DocumentMap) by their [`alias`](Document::alias)es.
 ///
@@ -31,11 +82,12 @@ pub use into_result::IntoResult;
 ///
 /// Note: if any of the [`Document`](Document)s fail to be created, i.e. returns an error, the `closure` will NOT be run.
 /// Errors encountered during Document setup or returned from the closure will be printed.
+/// Use [`try_with`](try_with) instead to get the value returned from the closure, or the errors.
 ///
 /// Note: to conduct write operations, including `.append(...)` and `.replace(...)` on [`Document`](Document)s, declare the [`DocumentMap`](DocumentMap) parameter of *closure* to be mutable.
 ///
 /// e.g.
-/// ```
+/// ```ignore
 /// with(
 ///     [
 ///         Document::at(User(Pictures([])), "1.png", Create::No),
@@ -58,29 +110,366 @@ pub use into_result::IntoResult;
 ///         Ok(())
 ///     },
 /// );
+/// ```
 pub fn with<Documents, Closure, Return>(documents: Documents, closure: Closure)
 where
-    Documents: IntoIterator<Item = Result<Document, Box<dyn Error>>>,
+    Documents: IntoIterator<Item = Result<Document, DocumentError>>,
     Closure: FnOnce(DocumentMap) -> Return,
     Return: IntoResult,
 {
-    let mut document_map = HashMap::new();
-    for document_result in documents.into_iter() {
-        let document = match document_result {
-            Ok(document) => document.clone(),
-            Err(error) => {
-                eprintln!("{}", error);
-                return;
+    if let Err(error) = try_with(documents, closure) {
+        eprintln!("{error}");
+    }
+}
+
+/// Like [`with`](with), but returns what happened instead of printing errors.
+///
+/// Returns the value returned from the `closure` (e.g. `T` if it returns `Result<T, Box<dyn Error>>` or `Option<T>`).
+///
+/// Returns [`WithError::Setup`](WithError::Setup) listing every [`Document`](Document) which failed to be created and why,
+/// in which case the `closure` will NOT be run. Returns [`WithError::Closure`](WithError::Closure) if the `closure` returned an error.
+///
+/// Note: if this function returns an error or the `closure` panics, the files and folders created by the [`Create`](Create) policies of the Documents are removed again.
+///
+/// e.g.
+/// ```ignore
+/// let word_count = try_with(
+///     [Document::at(User(Documents([])), "essay.txt", Create::No)],
+///     |d| Some(d["essay.txt"].content().ok()?.split_whitespace().count()),
+/// )?;
+/// ```
+pub fn try_with<Documents, Closure, Return>(
+    documents: Documents,
+    closure: Closure,
+) -> Result<Return::Output, WithError>
+where
+    Documents: IntoIterator<Item = Result<Document, DocumentError>>,
+    Closure: FnOnce(DocumentMap) -> Return,
+    Return: IntoResult,
+{
+    try_with_options(move || documents, WithOptions::default(), closure)
+}
+
+/// Like [`try_with`](try_with), but with [`WithOptions`](WithOptions), e.g. to only commit writes made in the `closure` if it succeeds.
+///
+/// *documents*: a function which creates the Documents, e.g. `|| [Document::at(User(Documents([])), "notes.txt", Create::No)]`.
+/// It is called after the [`roots`](WithOptions::roots) and [`backend`](WithOptions::backend) of the `options` are entered,
+/// so that the Documents are resolved with them.
+pub fn try_with_options<MakeDocuments, Documents, Closure, Return>(
+    documents: MakeDocuments,
+    options: WithOptions,
+    closure: Closure,
+) -> Result<Return::Output, WithError>
+where
+    MakeDocuments: FnOnce() -> Documents,
+    Documents: IntoIterator<Item = Result<Document, DocumentError>>,
+    Closure: FnOnce(DocumentMap) -> Return,
+    Return: IntoResult,
+{
+    options.check()?;
+    let _roots = options.roots.clone().map(enter_roots);
+    let _backend = options.backend.clone().map(enter_backend);
+    let (document_map, transaction) =
+        set_up(documents(), options.stage_writes, options.lock).map_err(WithError::Setup)?;
+    // If the closure panics, the transaction is rolled back when it is dropped.
+    match closure(document_map).into_result() {
+        Ok(value) => {
+            transaction.commit().map_err(WithError::Commit)?;
+            Ok(value)
+        }
+        Err(error) => {
+            drop(transaction);
+            Err(WithError::Closure(error))
+        }
+    }
+}
+
+/// Like [`with`](with), but the `closure` returns a future, e.g. an `async` block, which is awaited.
+///
+/// The Documents are given to the closure in a [`DocumentMap`](DocumentMap) by their [`alias`](Document::alias)es, just like in `with`.
+/// Use the async methods of [`Document`](Document), such as [`append_async`](Document::append_async), inside the closure so that it does not block the executor.
+///
+/// Errors encountered during Document setup or returned from the closure will be printed.
+/// Use [`try_with_async`](try_with_async) instead to get the value returned from the closure, or the errors.
+///
+/// Requires the `async` feature, and must be called from within a tokio runtime.
+///
+/// ```ignore
+/// with_async(
+///     [Document::at_async(User(Documents([])), "log.txt", Create::OnlyIfNotExists).await],
+///     |mut d| async move {
+///         d["log.txt"].append_async(b"Started\n").await?;
+///         Ok(())
+///     },
+/// )
+/// .await;
+/// ```
+#[cfg(feature = "async")]
+pub async fn with_async<Documents, Closure, Future>(documents: Documents, closure: Closure)
+where
+    Documents: IntoIterator<Item = Result<Document, DocumentError>>,
+    Closure: FnOnce(DocumentMap) -> Future,
+    Future: std::future::Future,
+    Future::Output: IntoResult,
+{
+    if let Err(error) = try_with_async(documents, closure).await {
+        eprintln!("{error}");
+    }
+}
+
+/// Like [`try_with`](try_with), but the `closure` returns a future, e.g. an `async` block, which is awaited.
+///
+/// Returns the value returned from the future, [`WithError::Setup`](WithError::Setup) if any of the Documents failed to be created,
+/// or [`WithError::Closure`](WithError::Closure) if the future returned an error.
+/// Like `try_with`, the files and folders created by the [`Create`](Create) policies of the Documents are removed again if this function returns an error.
+///
+/// Requires the `async` feature, and must be called from within a tokio runtime.
+#[cfg(feature = "async")]
+pub async fn try_with_async<Documents, Closure, Future>(
+    documents: Documents,
+    closure: Closure,
+) -> Result<<Future::Output as IntoResult>::Output, WithError>
+where
+    Documents: IntoIterator<Item = Result<Document, DocumentError>>,
+    Closure: FnOnce(DocumentMap) -> Future,
+    Future: std::future::Future,
+    Future::Output: IntoResult,
+{
+    let documents: Vec<_> = documents.into_iter().collect();
+    try_with_async_options(move || documents, WithOptions::default(), closure).await
+}
+
+/// Like [`try_with_options`](try_with_options), but the `closure` returns a future, e.g. an `async` block, which is awaited.
+///
+/// The `documents` function is called on a blocking thread of the tokio runtime, after the [`roots`](WithOptions::roots) and
+/// [`backend`](WithOptions::backend) of the `options` are entered there, and the files are locked and staged on that thread too.
+///
+/// Note: the future may run on any thread of the runtime, so the `roots` and `backend` of the `options` are NOT entered while it runs.
+/// Documents created inside the future use the Roots and Backend of the thread they are created on; create them in `documents` instead.
+///
+/// Committing and rolling back also happen on a blocking thread, including when the closure or the future panics; the panic is resumed afterwards.
+/// Only if the returned future is dropped before it completes, e.g. when it is cancelled by `tokio::select!` or a timeout,
+/// is the transaction rolled back synchronously on the thread which drops it, blocking that thread of the executor until the files are removed.
+///
+/// Requires the `async` feature, and must be called from within a tokio runtime.
+///
+/// ```ignore
+/// try_with_async_options(
+///     || [Document::at(User(Documents([])), "report.txt", Create::No)],
+///     WithOptions::new().stage_writes(true),
+///     |mut d| async move {
+///         d["report.txt"].append_async(b"Q3 figures").await?;
+///         Ok(())
+///     },
+/// )
+/// .await?;
+/// ```
+#[cfg(feature = "async")]
+pub async fn try_with_async_options<MakeDocuments, Documents, Closure, Future>(
+    documents: MakeDocuments,
+    options: WithOptions,
+    closure: Closure,
+) -> Result<<Future::Output as IntoResult>::Output, WithError>
+where
+    MakeDocuments: FnOnce() -> Documents + Send + 'static,
+    Documents: IntoIterator<Item = Result<Document, DocumentError>>,
+    Closure: FnOnce(DocumentMap) -> Future,
+    Future: std::future::Future,
+    Future::Output: IntoResult,
+{
+    options.check()?;
+    let (document_map, transaction) = async_document::blocking(move || {
+        let _roots = options.roots.clone().map(enter_roots);
+        let _backend = options.backend.clone().map(enter_backend);
+        set_up(documents(), options.stage_writes, options.lock)
+    })
+    .await
+    .map_err(WithError::Setup)?;
+    // A panic in the closure or the future is caught, so that the transaction is rolled back on a blocking thread before it goes on.
+    let output =
+        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || closure(document_map)))
+        {
+            Ok(future) => {
+                let mut future = std::pin::pin!(future);
+                std::future::poll_fn(|context| {
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        future.as_mut().poll(context)
+                    }))
+                    .map_or_else(
+                        |panic| std::task::Poll::Ready(Err(panic)),
+                        |poll| poll.map(Ok),
+                    )
+                })
+                .await
             }
+            Err(panic) => Err(panic),
         };
+    match output.map(IntoResult::into_result) {
+        Ok(Ok(value)) => {
+            async_document::blocking(move || transaction.commit())
+                .await
+                .map_err(WithError::Commit)?;
+            Ok(value)
+        }
+        Ok(Err(error)) => {
+            async_document::blocking(move || drop(transaction)).await;
+            Err(WithError::Closure(error))
+        }
+        Err(panic) => {
+            async_document::blocking(move || drop(transaction)).await;
+            std::panic::resume_unwind(panic)
+        }
+    }
+}
+
+/// What has to be committed or undone after the closure of [`try_with_options`](try_with_options) has run:
+/// the files and folders created by the [`Create`](Create) policies of the Documents, and their staged copies.
+///
+/// It is rolled back when it is dropped without being committed, e.g. when the closure returns an error or panics.
+/// The locks on the files are released afterwards.
+struct Transaction {
+    created: Vec<(BackendRef, Vec<PathBuf>)>,
+    staged: Vec<(BackendRef, Staging, PathBuf)>,
+    committed: bool,
+    _locks: Vec<DocumentLock>,
+}
+
+/// Collect `documents` into a [`DocumentMap`](DocumentMap) by their aliases, locking their files with `lock` if set
+/// and staging their writes if `stage_writes` is set.
+///
+/// Returns every Document which failed to be created, locked or staged and why, after undoing the setup of the others.
+fn set_up(
+    documents: impl IntoIterator<Item = Result<Document, DocumentError>>,
+    stage_writes: bool,
+    lock: Option<LockKind>,
+) -> Result<(DocumentMap, Transaction), Vec<(usize, DocumentError)>> {
+    let mut set_up_documents = Vec::new();
+    let mut setup_errors = Vec::new();
+    for (index, document_result) in documents.into_iter().enumerate() {
+        match document_result {
+            Ok(document) => set_up_documents.push((index, document)),
+            Err(error) => setup_errors.push((index, error)),
+        }
+    }
+    let mut locks = Vec::new();
+    if let (Some(kind), true) = (lock, setup_errors.is_empty()) {
+        // Locked before staging, so that the staged copies are made of files no other process is writing.
+        let mut in_alias_order: Vec<_> = set_up_documents.iter().collect();
+        in_alias_order
+            .sort_by(|(_, a), (_, b)| (&a.alias, &a.pathbuf).cmp(&(&b.alias, &b.pathbuf)));
+        let mut locked: Vec<(&BackendRef, &PathBuf)> = Vec::new();
+        for (index, document) in in_alias_order {
+            // Two locks on the same file wait for each other, even in the same process.
+            if locked.contains(&(&document.backend, &document.pathbuf)) {
+                continue;
+            }
+            match document.lock(kind) {
+                Ok(document_lock) => {
+                    locks.push(document_lock);
+                    locked.push((&document.backend, &document.pathbuf));
+                }
+                Err(error) => {
+                    setup_errors.push((*index, error));
+                    break;
+                }
+            }
+        }
+    }
+    // Moved out, so that a Document which outlives this transaction does not remove its files on a later failure.
+    let created = set_up_documents
+        .iter_mut()
+        .map(|(_, document)| {
+            (
+                document.backend.clone(),
+                std::mem::take(&mut document.created),
+            )
+        })
+        .collect();
+    if stage_writes && setup_errors.is_empty() {
+        for (index, document) in set_up_documents.iter_mut() {
+            if let Err(error) = document.stage() {
+                setup_errors.push((*index, error));
+            }
+        }
+    }
+    let transaction = Transaction {
+        created,
+        staged: set_up_documents
+            .iter()
+            .filter(|(_, document)| document.staging.pathbuf().is_some())
+            .map(|(_, document)| {
+                (
+                    document.backend.clone(),
+                    document.staging.clone(),
+                    document.pathbuf.clone(),
+                )
+            })
+            .collect(),
+        committed: false,
+        _locks: locks,
+    };
+    if !setup_errors.is_empty() {
+        drop(transaction);
+        return Err(setup_errors);
+    }
+    let mut document_map = HashMap::new();
+    for (_, document) in set_up_documents {
         let document_alias = document.alias.clone();
         if document_alias != "_" {
             document_map.insert(document_alias, document);
         }
     }
-    match closure(DocumentMap(document_map)).into_result() {
-        Ok(_) => {}
-        Err(error) => eprintln!("{error}"),
+    Ok((DocumentMap(document_map), transaction))
+}
+
+impl Transaction {
+    /// Rename each staged copy over its file.
+    ///
+    /// Each rename is atomic, but the commit is not atomic across Documents: if one fails, the files renamed before it keep their new content.
+    /// The transaction is then rolled back when it is dropped: the remaining copies are removed,
+    /// and so are the created files and folders, unless a file has already been renamed into them.
+    /// Either way, the Documents read and write their files directly again afterwards.
+    fn commit(mut self) -> Result<(), DocumentError> {
+        let mut renamed_any = false;
+        for (backend, staging, pathbuf) in &self.staged {
+            let Some(staged_pathbuf) = staging.finish() else {
+                continue;
+            };
+            let backend = backend.0.as_ref();
+            let result = sync_file(backend, &staged_pathbuf)
+                .and_then(|_| rename_durably(backend, &staged_pathbuf, pathbuf));
+            if let Err(error) = result {
+                let _ = backend.remove_file(&staged_pathbuf);
+                if renamed_any {
+                    // The created files may hold committed content by now.
+                    self.created.clear();
+                }
+                return Err(DocumentError::CouldNotWriteFile(pathbuf.path(), error));
+            }
+            renamed_any = true;
+        }
+        self.committed = true;
+        Ok(())
+    }
+
+    /// Remove the staged copies, and the files and folders created by the Create policies.
+    fn roll_back(&self) {
+        for (backend, staging, _) in &self.staged {
+            if let Some(staged_pathbuf) = staging.finish() {
+                let _ = backend.0.remove_file(&staged_pathbuf);
+            }
+        }
+        for (backend, created) in &self.created {
+            remove_created(backend.0.as_ref(), created);
+        }
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.roll_back();
+        }
     }
 }
 
@@ -92,18 +481,48 @@ where
 /// use documents::prelude::*;
 /// ```
 pub mod prelude {
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    pub use crate::trash::TrashItem;
     #[allow(unused_imports)]
     pub use crate::{
+        access::{Access, ReadOnly, ReadWrite},
+        backend::{reset_backend, set_backend, Backend, FileHandle, InMemory, LocalDisk},
         create::Create,
-        document::{Document, LinesBufReaderFileExt, ResultDocumentBoxErrorExt},
+        document::{Document, LinesBufReaderFileExt, ResultDocumentExt},
+        document_error::{DocumentError, DocumentErrorKind},
+        durability::Durability,
+        faulty::{Fault, Faulty, Operation},
         filesystem_entity::FileSystemEntity,
         folder::{
             Folder::{self, Project, User},
-            Project::{Config, Data},
-            User::{Documents, Downloads, Home, Pictures, Videos},
+            Project::{Cache, Config, Data, DataLocal, Preference, Runtime, State},
+            User::{
+                Desktop, Documents, Downloads, Fonts, Home, Music, Pictures, Public, Templates,
+                Videos,
+            },
         },
+        folder_path::{AppId, FolderPath, KnownFolder},
+        format::{Format, ParseError},
+        lock::{DocumentLock, LockKind},
+        metadata::{EntryKind, Metadata},
         mode::Mode,
+        open_document::OpenDocument,
+        roots::{reset_roots, set_roots, Roots, Sandbox, SystemRoots},
+        search_path::SearchPath,
+        try_with, try_with_options,
+        walk::{SortOrder, Walk},
         with,
+        with_error::WithError,
+        with_options::WithOptions,
+    };
+    #[cfg(feature = "async")]
+    pub use crate::{
+        async_document::AsyncLines, try_with_async, try_with_async_options, with_async,
+    };
+    #[cfg(feature = "settings")]
+    pub use crate::{
+        search_path::Merged,
+        settings::{Schema, Settings},
     };
 }
 
@@ -112,13 +531,32 @@ mod test {
     use std::path::PathBuf;
 
     use super::prelude::*;
+
+    /// Held by tests which depend on the process-wide Roots set with `set_roots`:
+    /// every test which resolves well-known folders without entering Roots of its own.
+    static ENV: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// A fresh folder under the system temp folder, so tests don't touch each other's files.
+    fn scratch(name: &str) -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("documents-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
     #[test]
-    /// This test doesn't do anything yet.
+    /// Documents which could not be set up are reported by their position, and the closure runs once they all exist.
     fn test1() {
-        with(
+        let sandbox = Sandbox::new().unwrap();
+        let _roots = sandbox.clone().enter();
+        let documents = || {
             [
                 Document::at(User(Pictures([])), "1.png", Create::No),
-                Document::at(User(Documents([])), "README.txt", Create::AutoRenameIfExists),
+                Document::at(
+                    User(Documents([])),
+                    "README.txt",
+                    Create::AutoRenameIfExists,
+                ),
                 Document::at(
                     User(Pictures(["Movie Trailer"])),
                     "thumbnail.png",
@@ -126,25 +564,46 @@ mod test {
                 )
                 .alias("pic"),
                 Document::at(User(Downloads([])), "file.txt", Create::No),
-            ],
-            |mut d| {
-                for (alias, doc) in d.clone() {
-                    println!("{alias}: {doc:?}");
-                }
-                println!("{}", d["1.png"].name());
-                d["pic"].launch_with_default_app()?;
-                d["file.txt"]
+            ]
+        };
+        let Err(WithError::Setup(errors)) = try_with(documents(), |_| None::<()>) else {
+            panic!("the missing files were not reported");
+        };
+        let failed: Vec<usize> = errors.iter().map(|(index, _)| *index).collect();
+        assert_eq!(failed, [0, 2, 3]);
+        assert!(errors
+            .iter()
+            .all(|(_, error)| error.kind() == DocumentErrorKind::NotFound));
+        let home = sandbox.root().join("home");
+        assert!(!home.join("Documents").join("README.txt").exists());
+        std::fs::create_dir(home.join("Pictures").join("Movie Trailer")).unwrap();
+        for fixture in [
+            "Pictures/1.png",
+            "Pictures/Movie Trailer/thumbnail.png",
+            "Downloads/file.txt",
+        ] {
+            std::fs::write(home.join(fixture), "").unwrap();
+        }
+        let lines = try_with(
+            documents(),
+            |mut d| -> Result<Vec<String>, Box<dyn std::error::Error>> {
+                assert_eq!(d.aliases(), ["1.png", "README.txt", "file.txt", "pic"]);
+                assert_eq!(d["pic"].name(), "thumbnail.png");
+                let lines = d["file.txt"]
                     .append(b"Something\nto be added")?
-                    .launch_with_default_app()?
                     .lines()?
-                    .print()?;
-                Ok(())
+                    .collect::<Result<_, _>>()?;
+                Ok(lines)
             },
-        );
+        )
+        .unwrap();
+        assert_eq!(lines, ["Something", "to be added"]);
+        assert!(home.join("Documents").join("README.txt").exists());
     }
     #[test]
     /// This test also doesn't do anything yet.
     fn test2() {
+        let _roots = Sandbox::new().unwrap().enter();
         let a: &[&dyn FileSystemEntity] = &[
             &Document::at(User(Pictures([])), "pic", Create::No),
             &User(Pictures([])),
@@ -158,4 +617,1458 @@ mod test {
             );
         }
     }
+    #[test]
+    /// Bytes that are not valid UTF-8 can be read whole, by range, in chunks and into a writer.
+    fn binary_content() {
+        let path = scratch("binary_content").join("blob.bin");
+        let blob: Vec<u8> = (0..=255).collect();
+        std::fs::write(&path, &blob).unwrap();
+        let document = Document::at_path(path.display(), "blob", Create::No).unwrap();
+        assert!(document.content().is_err());
+        assert_eq!(document.bytes().unwrap(), blob);
+        assert_eq!(document.bytes_range(250, 10).unwrap(), blob[250..]);
+        let chunks: Vec<Vec<u8>> = document.chunks(100).unwrap().map(Result::unwrap).collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.concat(), blob);
+        assert_eq!(document.chunks(0).unwrap().count(), 256);
+        let mut copy = Vec::new();
+        assert_eq!(document.read_into(&mut copy).unwrap(), 256);
+        assert_eq!(copy, blob);
+    }
+    #[test]
+    /// Replacing atomically swaps in the new content and leaves no temporary files behind.
+    fn replace_atomically() {
+        let folder = scratch("replace_atomically");
+        let path = folder.join("settings.toml");
+        std::fs::write(&path, "theme = \"light\"").unwrap();
+        let mut document = Document::at_path(path.display(), "settings", Create::No).unwrap();
+        document.replace_atomically(b"theme = \"dark\"").unwrap();
+        assert_eq!(document.content().unwrap(), "theme = \"dark\"");
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{symlink, PermissionsExt};
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            document.replace_atomically(b"theme = \"light\"").unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            // The temporary file is synced through the handle it was written with, so a file without a read bit can be replaced too.
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o200)).unwrap();
+            {
+                let _backend = Faulty::new(LocalDisk)
+                    .fail(Fault::new(Operation::Open).error(std::io::ErrorKind::PermissionDenied))
+                    .enter();
+                Document::at_path(path.display(), "settings", Create::No)
+                    .unwrap()
+                    .replace_atomically(b"theme = \"dark\"")
+                    .unwrap();
+            }
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+            document.replace_atomically(b"theme = \"light\"").unwrap();
+            let link = folder.join("link.toml");
+            symlink(&path, &link).unwrap();
+            let mut linked = Document::at_path(link.display(), "link", Create::No).unwrap();
+            linked.replace_atomically(b"theme = \"dark\"").unwrap();
+            assert!(std::fs::symlink_metadata(&link).unwrap().is_file());
+            assert_eq!(std::fs::read_to_string(&path).unwrap(), "theme = \"light\"");
+        }
+    }
+    #[test]
+    /// Errors from flushing buffered writes are returned instead of being dropped.
+    fn write_errors_are_surfaced() {
+        let path = scratch("write_errors_are_surfaced").join("log.txt");
+        let mut document = Document::at_path(path.display(), "log", Create::OnlyIfNotExists)
+            .durability(Durability::SyncAll)
+            .unwrap();
+        document
+            .append(b"first\n")
+            .unwrap()
+            .append(b"second\n")
+            .unwrap();
+        assert_eq!(document.content().unwrap(), "first\nsecond\n");
+        if PathBuf::from("/dev/full").exists() {
+            let mut full = Document::at_path("/dev/full", "full", Create::No).unwrap();
+            let error = full.append(b"no space").unwrap_err();
+            assert!(matches!(error, DocumentError::CouldNotWriteFile(..)));
+            assert_eq!(error.kind(), DocumentErrorKind::StorageFull);
+        }
+    }
+    #[test]
+    /// Replacing with shorter content leaves nothing of the old content behind.
+    fn replace_with_shorter_content() {
+        let path = scratch("replace_with_shorter_content").join("note.txt");
+        std::fs::write(&path, "a much longer old content").unwrap();
+        let mut document = Document::at_path(path.display(), "note", Create::No).unwrap();
+        document.replace_with(b"short").unwrap();
+        assert_eq!(document.content().unwrap(), "short");
+        let mut open = document.open(Mode::ReadReplace).unwrap();
+        assert_eq!(open.content().unwrap(), "");
+    }
+    #[test]
+    /// Errors keep the path, the failed operation and the original io::Error.
+    fn structured_errors() {
+        use std::error::Error;
+        let folder = scratch("structured_errors");
+        let missing = Document::at_path(folder.join("missing.txt").display(), "m", Create::No);
+        let error = missing.unwrap_err();
+        assert_eq!(error.kind(), DocumentErrorKind::NotFound);
+        assert_eq!(
+            error.path(),
+            Some(folder.join("missing.txt").path().as_str())
+        );
+        let path = folder.join("folder.txt");
+        std::fs::create_dir(&path).unwrap();
+        let mut document = Document::at_path(path.display(), "folder", Create::No).unwrap();
+        let error = document.append(b"text").unwrap_err();
+        assert!(matches!(error, DocumentError::CouldNotOpenFile(..)));
+        assert_eq!(error.kind(), DocumentErrorKind::IsADirectory);
+        assert!(error.source().is_some());
+    }
+    #[test]
+    /// try_with returns the closure's value, or every Document which failed setup.
+    fn try_with_returns_results() {
+        let folder = scratch("try_with_returns_results");
+        let path = folder.join("essay.txt");
+        std::fs::write(&path, "one two three").unwrap();
+        let word_count = try_with(
+            [Document::at_path(path.display(), "essay", Create::No)],
+            |d| Some(d["essay"].content().ok()?.split_whitespace().count()),
+        );
+        assert_eq!(word_count.unwrap(), 3);
+        let result = try_with(
+            [
+                Document::at_path(folder.join("a.txt").display(), "a", Create::No),
+                Document::at_path(path.display(), "essay", Create::No),
+                Document::at_path(folder.join("b.txt").display(), "b", Create::No),
+            ],
+            |_| -> () { panic!("closure should not run") },
+        );
+        match result {
+            Err(WithError::Setup(errors)) => {
+                let indices: Vec<usize> = errors.iter().map(|(index, _)| *index).collect();
+                assert_eq!(indices, [0, 2]);
+            }
+            _ => panic!("expected setup errors"),
+        }
+        let result = try_with(
+            [Document::at_path(path.display(), "essay", Create::No)],
+            |d| d["essay"].bytes_range(100, 1).ok()?.first().copied(),
+        );
+        assert!(matches!(result, Err(WithError::Closure(_))));
+    }
+    #[test]
+    /// Files and folders created during setup are removed again when try_with fails.
+    fn rollback_created_files() {
+        let folder = scratch("rollback_created_files");
+        let new_folder = folder.join("new").join("nested");
+        let result = try_with(
+            [
+                Document::at_path(
+                    new_folder.join("a.txt").display(),
+                    "a",
+                    Create::OnlyIfNotExists,
+                ),
+                Document::at_path(folder.join("missing.txt").display(), "b", Create::No),
+            ],
+            |_| (),
+        );
+        assert!(matches!(result, Err(WithError::Setup(_))));
+        assert!(!folder.join("new").exists());
+        let result = try_with(
+            [Document::at_path(
+                folder.join("c.txt").display(),
+                "c",
+                Create::OnlyIfNotExists,
+            )],
+            |_| None::<()>,
+        );
+        assert!(matches!(result, Err(WithError::Closure(_))));
+        assert!(!folder.join("c.txt").exists());
+    }
+
+    #[test]
+    /// Staged writes only reach the file if the closure succeeds.
+    fn staged_writes() {
+        let path = scratch("staged_writes").join("report.txt");
+        std::fs::write(&path, "a long first draft").unwrap();
+        let staged = WithOptions::new().stage_writes(true);
+        let result = try_with_options(
+            || [Document::at_path(path.display(), "report", Create::No)],
+            staged.clone(),
+            |mut d| -> Result<(), Box<dyn std::error::Error>> {
+                d["report"].replace_with(b"final")?;
+                assert_eq!(d["report"].content()?, "final");
+                Err("changed my mind")?
+            },
+        );
+        assert!(matches!(result, Err(WithError::Closure(_))));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "a long first draft"
+        );
+        let mut d = try_with_options(
+            || [Document::at_path(path.display(), "report", Create::No)],
+            staged,
+            |mut d| {
+                d["report"].replace_with(b"final").ok()?;
+                Some(d)
+            },
+        )
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "final");
+        assert_eq!(
+            std::fs::read_dir(path.parent().unwrap()).unwrap().count(),
+            1
+        );
+        // Once committed, the returned Document writes to the file itself.
+        d["report"].append(b", signed").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "final, signed");
+        // While staged, a Document can be copied with its staged content, but not moved or deleted.
+        let _roots = Sandbox::new().unwrap().enter();
+        try_with_options(
+            || {
+                [
+                    Document::at(User(Documents([])), "report.txt", Create::OnlyIfNotExists)
+                        .alias("report"),
+                ]
+            },
+            WithOptions::new().stage_writes(true),
+            |mut d| {
+                d["report"].replace_with(b"staged").ok()?;
+                let copy = d["report"]
+                    .copy_to(User(Desktop([])), Create::OnlyIfNotExists)
+                    .ok()?;
+                assert_eq!(copy.content().ok()?, "staged");
+                assert!(d["report"]
+                    .move_to(User(Desktop(["Drafts"])), Create::OnlyIfNotExists)
+                    .is_err());
+                assert!(d["report"]
+                    .rename("final.txt", Create::OnlyIfNotExists)
+                    .is_err());
+                assert!(d["report"].delete().is_err());
+                Some(())
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            Document::at(User(Documents([])), "report.txt", Create::No)
+                .unwrap()
+                .content()
+                .unwrap(),
+            "staged"
+        );
+    }
+    #[test]
+    /// A Document which outlives its try_with does not remove its file when a later try_with fails.
+    fn reused_documents() {
+        let path = scratch("reused_documents").join("notes.txt");
+        let mut d = try_with(
+            [Document::at_path(
+                path.display(),
+                "notes",
+                Create::OnlyIfNotExists,
+            )],
+            Some,
+        )
+        .unwrap();
+        d["notes"].replace_with(b"important data").unwrap();
+        let notes = d["notes"].clone();
+        let result = try_with([Ok(notes)], |_| None::<()>);
+        assert!(matches!(result, Err(WithError::Closure(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "important data");
+    }
+    #[test]
+    /// A panicking closure removes the created files and the staged copies, and a failed commit keeps what it already committed.
+    fn transaction_cleanup() {
+        let folder = scratch("transaction_cleanup");
+        let existing = folder.join("existing.txt");
+        std::fs::write(&existing, "kept").unwrap();
+        let documents = || {
+            [
+                Document::at_path(
+                    folder.join("new").join("created.txt").display(),
+                    "created",
+                    Create::OnlyIfNotExists,
+                ),
+                Document::at_path(existing.display(), "existing", Create::No),
+            ]
+        };
+        let staged = WithOptions::new().stage_writes(true);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            try_with_options(documents, staged.clone(), |mut d| -> Option<()> {
+                d["existing"].replace_with(b"changed").unwrap();
+                panic!("closure panicked");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 1);
+        let faulty =
+            Faulty::new(LocalDisk).fail(Fault::new(Operation::Rename).path("existing.txt"));
+        let _backend = faulty.enter();
+        let result = try_with_options(documents, staged, |mut d| {
+            d["created"].replace_with(b"new").ok()?;
+            d["existing"].replace_with(b"changed").ok()?;
+            Some(())
+        });
+        assert!(matches!(result, Err(WithError::Commit(_))));
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "kept");
+        // The created file was committed before the failure, so it is kept.
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 2);
+        assert_eq!(
+            std::fs::read_to_string(folder.join("new").join("created.txt")).unwrap(),
+            "new"
+        );
+    }
+    #[test]
+    /// A FolderPath built at runtime resolves to the same place as the equivalent Folder.
+    fn folder_path() {
+        let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+        let subfolders = vec!["Invoices".to_string(), "2024".to_string()];
+        let mut folder_path = FolderPath::new(KnownFolder::Home);
+        for subfolder in &subfolders {
+            folder_path.push(subfolder);
+        }
+        assert_eq!(
+            folder_path,
+            FolderPath::from(User(Home(["Invoices", "2024"])))
+        );
+        assert_eq!(folder_path.subfolders(), subfolders);
+        assert_eq!(folder_path.path(), User(Home(["Invoices", "2024"])).path());
+        let march = folder_path.join("March");
+        assert_eq!(march.parent(), Some(folder_path.clone()));
+        assert_eq!(FolderPath::new(KnownFolder::Home).parent(), None);
+    }
+    #[test]
+    /// The extra user folders convert to their KnownFolders and resolve like the directories crate.
+    fn extra_user_folders() {
+        let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+        assert_eq!(
+            FolderPath::from(User(Music([]))).base(),
+            &KnownFolder::Music
+        );
+        assert_eq!(
+            FolderPath::from(User(Templates(["Letters"]))).base(),
+            &KnownFolder::Templates
+        );
+        if let Some(dirs) = directories::UserDirs::new() {
+            assert_eq!(
+                User(Desktop([])).exists(),
+                dirs.desktop_dir().is_some_and(|path| path.exists())
+            );
+            match dirs.font_dir() {
+                Some(font_dir) => {
+                    assert_eq!(PathBuf::from(User(Fonts(["a"])).path()), font_dir.join("a"))
+                }
+                None => assert!(matches!(
+                    Document::at(User(Fonts([])), "font.ttf", Create::No),
+                    Err(DocumentError::FontsDirNotFound)
+                )),
+            }
+        }
+    }
+    #[test]
+    /// The extra project folders resolve through ProjectDirs, and missing ones return their own errors.
+    fn extra_project_folders() {
+        let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+        let dirs = directories::ProjectDirs::from("com", "example", "App").unwrap();
+        let cache = Project(Cache(["thumbnails"]).with_id("com", "example", "App"));
+        assert_eq!(
+            PathBuf::from(cache.path()),
+            dirs.cache_dir().join("thumbnails")
+        );
+        let data_local = Project(DataLocal([]).with_id("com", "example", "App"));
+        assert_eq!(PathBuf::from(data_local.path()), dirs.data_local_dir());
+        let runtime = Document::at(
+            Project(Runtime([]).with_id("com", "example", "App")),
+            "app.sock",
+            Create::No,
+        );
+        match dirs.runtime_dir() {
+            Some(_) => assert!(!matches!(runtime, Err(DocumentError::RuntimeDirNotFound))),
+            None => assert!(matches!(runtime, Err(DocumentError::RuntimeDirNotFound))),
+        }
+    }
+    #[test]
+    /// Walking a folder honours the depth, hidden file, symlink and sort options.
+    fn walk_folder() {
+        let _roots = Sandbox::new().unwrap().enter();
+        let folder = PathBuf::from(User(Downloads([])).path());
+        std::fs::create_dir_all(folder.join("sub").join("deeper")).unwrap();
+        std::fs::create_dir_all(folder.join(".cache")).unwrap();
+        for file in ["b.txt", "a.txt", ".hidden", "sub/c.txt", "sub/deeper/d.txt"] {
+            std::fs::write(folder.join(file), file).unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(folder.join("sub"), folder.join("link")).unwrap();
+        let aliases =
+            |walk: Walk| -> Vec<String> { walk.map(|document| document.unwrap().alias).collect() };
+        let walk = || User(Downloads([])).walk().sort_by(SortOrder::Name);
+        assert_eq!(
+            aliases(walk()),
+            ["a.txt", "b.txt", "sub/c.txt", "sub/deeper/d.txt"]
+        );
+        assert_eq!(aliases(walk().max_depth(1)), ["a.txt", "b.txt"]);
+        assert!(aliases(walk().max_depth(0)).is_empty());
+        assert_eq!(
+            aliases(walk().max_depth(1).include_hidden(true)),
+            [".hidden", "a.txt", "b.txt"]
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            aliases(walk().max_depth(2).follow_symlinks(true)),
+            ["a.txt", "b.txt", "link/c.txt"]
+        );
+        let result = try_with(walk(), |d| d["sub/c.txt"].content().ok());
+        assert_eq!(result.unwrap(), "sub/c.txt");
+        let mut documents = aliases(User(Downloads([])).documents());
+        documents.sort();
+        assert_eq!(documents, ["a.txt", "b.txt"]);
+        let sub = FolderPath::from(User(Downloads(["sub"])));
+        assert_eq!(aliases(sub.documents()), ["c.txt"]);
+        let child_folders = User(Downloads([])).child_folders().unwrap();
+        #[cfg(unix)]
+        assert_eq!(
+            child_folders,
+            [FolderPath::from(User(Downloads(["link"]))), sub.clone()]
+        );
+        #[cfg(not(unix))]
+        assert_eq!(child_folders, [sub.clone()]);
+        assert_eq!(
+            sub.child_folders().unwrap(),
+            [FolderPath::from(User(Downloads(["sub", "deeper"])))]
+        );
+        assert!(sub.join("deeper").child_folders().unwrap().is_empty());
+        assert!(User(Downloads(["missing"])).child_folders().is_err());
+    }
+    #[test]
+    /// Glob patterns support *, ?, ** and character classes.
+    fn glob_patterns() {
+        use super::glob::glob_match;
+        assert!(glob_match("*.png", "shot.png"));
+        assert!(!glob_match("*.png", "2024/shot.png"));
+        assert!(glob_match("**/*.png", "shot.png"));
+        assert!(glob_match("**/*.png", "2024/March/shot.png"));
+        assert!(glob_match("2024/**", "2024/March/shot.png"));
+        assert!(glob_match("shot-?.png", "shot-1.png"));
+        assert!(!glob_match("shot-?.png", "shot-10.png"));
+        assert!(glob_match("report-[0-9][0-9].pdf", "report-07.pdf"));
+        assert!(!glob_match("report-[!0-9]*.pdf", "report-07.pdf"));
+        assert!(glob_match("[ab]*.txt", "beta.txt"));
+        assert!(glob_match("[unclosed.txt", "[unclosed.txt"));
+        assert!(!glob_match("*", ".hidden.png"));
+        assert!(glob_match(".*.png", ".hidden.png"));
+        assert!(!glob_match("**/*.png", ".config/shot.png"));
+        assert!(glob_match(".config/**", ".config/app/settings.toml"));
+        assert!(glob_match("**/.git/*", "src/.git/HEAD"));
+        // Would take exponential time with a recursive matcher.
+        let name = "a".repeat(100);
+        assert!(!glob_match(&format!("{}b", "*a".repeat(20)), &name));
+        let path = vec!["a"; 100].join("/");
+        assert!(!glob_match(&format!("{}b", "**/a/".repeat(20)), &path));
+    }
+    #[test]
+    /// Matching Documents are found in subfolders and aliased by their relative paths.
+    fn matching_documents() {
+        let _roots = Sandbox::new().unwrap().enter();
+        let folder = PathBuf::from(User(Pictures([])).path());
+        std::fs::create_dir_all(folder.join("Screenshots").join("2024")).unwrap();
+        std::fs::create_dir_all(folder.join(".thumbnails")).unwrap();
+        for file in [
+            "b.png",
+            "a.png",
+            "c.jpg",
+            ".hidden.png",
+            ".thumbnails/e.png",
+            "Screenshots/c.png",
+            "Screenshots/2024/d.png",
+        ] {
+            std::fs::write(folder.join(file), file).unwrap();
+        }
+        let matching = |pattern: &str| -> Vec<String> {
+            Document::matching(User(Pictures([])), pattern)
+                .map(|document| document.unwrap().alias)
+                .collect()
+        };
+        assert_eq!(matching("*.png"), ["a.png", "b.png"]);
+        assert_eq!(matching("Screenshots/*.png"), ["Screenshots/c.png"]);
+        assert_eq!(
+            matching("**/*.png"),
+            [
+                "Screenshots/2024/d.png",
+                "Screenshots/c.png",
+                "a.png",
+                "b.png"
+            ]
+        );
+        assert_eq!(matching("?.[!p]*"), ["c.jpg"]);
+        assert_eq!(matching(".hidden.png"), [".hidden.png"]);
+        assert_eq!(matching(".thumbnails/*.png"), [".thumbnails/e.png"]);
+        assert_eq!(matching(".*/**"), [".hidden.png", ".thumbnails/e.png"]);
+        let content = try_with(Document::matching(User(Pictures([])), "**/*.png"), |d| {
+            d["Screenshots/2024/d.png"].content().ok()
+        });
+        assert_eq!(content.unwrap(), "Screenshots/2024/d.png");
+        let documents: Vec<_> =
+            Document::matching(User(Pictures(["no-such-folder"])), "**/*.png").collect();
+        assert_eq!(documents.len(), 1);
+        assert!(documents[0].is_err());
+    }
+    #[test]
+    /// Renaming follows the Create policy at the new name, and deleting removes the file.
+    fn rename_and_delete() {
+        let folder = scratch("rename_and_delete");
+        std::fs::write(folder.join("final.txt"), "final").unwrap();
+        let draft = folder.join("draft.txt");
+        std::fs::write(&draft, "draft").unwrap();
+        let mut document = Document::at_path(draft.display(), "draft", Create::No).unwrap();
+        assert!(matches!(
+            document.rename("missing.txt", Create::No),
+            Err(DocumentError::FileNotFound(_))
+        ));
+        document
+            .rename("final.txt", Create::AutoRenameIfExists)
+            .unwrap();
+        assert_eq!(document.name(), "final(1).txt");
+        assert_eq!(document.alias, "draft");
+        assert!(!draft.exists());
+        assert_eq!(
+            std::fs::read_to_string(folder.join("final.txt")).unwrap(),
+            "final"
+        );
+        document
+            .rename("final.txt", Create::OnlyIfNotExists)
+            .unwrap();
+        assert_eq!(document.content().unwrap(), "draft");
+        document.delete().unwrap();
+        assert!(!document.exists());
+        assert_eq!(std::fs::read_dir(&folder).unwrap().count(), 0);
+    }
+    #[test]
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    /// Trashed files get a .trashinfo file, unique names in the trash, and can be listed and restored.
+    fn trash_and_restore() {
+        let folder = scratch("trash_and_restore");
+        let sandbox = Sandbox::new().unwrap();
+        let _roots = sandbox.clone().enter();
+        let trash = sandbox.root().join("home/.local/share/Trash");
+        let note = folder.join("note 1.txt");
+        std::fs::write(&note, "first").unwrap();
+        let mut document = Document::at_path(note.display(), "note", Create::No).unwrap();
+        let first = document.trash().unwrap();
+        assert!(!note.exists());
+        assert_eq!(first.original_path(), note);
+        assert_eq!(first.trashed_path(), trash.join("files").join("note 1.txt"));
+        let trash_info =
+            std::fs::read_to_string(trash.join("info").join("note 1.txt.trashinfo")).unwrap();
+        assert!(trash_info.starts_with("[Trash Info]\n"));
+        assert!(trash_info.contains("note%201.txt\n"));
+        assert!(trash_info.contains(&format!("DeletionDate={}", first.deletion_date())));
+        std::fs::write(&note, "second").unwrap();
+        let second = document.trash().unwrap();
+        assert_eq!(
+            second.trashed_path(),
+            trash.join("files").join("note 1(1).txt")
+        );
+        let listed = TrashItem::list().unwrap();
+        assert!(listed.contains(&first) && listed.contains(&second));
+        first.restore().unwrap();
+        assert_eq!(document.content().unwrap(), "first");
+        let error = second.restore().unwrap_err();
+        assert_eq!(error.kind(), DocumentErrorKind::AlreadyExists);
+        assert!(second.trashed_path().exists());
+        assert!(!TrashItem::list().unwrap().contains(&first));
+        let photos = folder.join("Photos");
+        std::fs::create_dir_all(photos.join("2024")).unwrap();
+        let trashed_folder = crate::trash::trash(&LocalDisk, &photos).unwrap();
+        std::fs::create_dir(&photos).unwrap();
+        std::fs::write(photos.join("new.png"), "").unwrap();
+        let error = trashed_folder.restore().unwrap_err();
+        assert_eq!(error.kind(), DocumentErrorKind::AlreadyExists);
+        std::fs::remove_dir_all(&photos).unwrap();
+        trashed_folder.restore().unwrap();
+        assert!(photos.join("2024").is_dir());
+        let link = folder.join("link.txt");
+        std::os::unix::fs::symlink(&note, &link).unwrap();
+        let trashed_link = Document::at_path(link.display(), "link", Create::No)
+            .unwrap()
+            .trash()
+            .unwrap();
+        assert!(std::fs::symlink_metadata(trashed_link.trashed_path())
+            .unwrap()
+            .is_symlink());
+        assert_eq!(document.content().unwrap(), "first");
+    }
+    #[test]
+    /// Metadata describes files, folders and symlinks, and the time and permission setters change it.
+    fn metadata_and_setters() {
+        let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+        let folder = scratch("metadata_and_setters");
+        let pathbuf = folder.join("notes.txt");
+        let mut document =
+            Document::at_path(pathbuf.display(), "notes", Create::OnlyIfNotExists).unwrap();
+        document.append(b"hello").unwrap();
+        let metadata = document.metadata().unwrap();
+        assert_eq!(metadata.kind, EntryKind::File);
+        assert_eq!(metadata.size, 5);
+        assert!(!metadata.is_symlink && !metadata.readonly);
+        assert_eq!(folder.metadata().unwrap().kind, EntryKind::Folder);
+        assert_eq!(User(Home([])).metadata().unwrap().kind, EntryKind::Folder);
+        assert_eq!(
+            folder.join("missing").metadata().unwrap_err().kind(),
+            DocumentErrorKind::NotFound
+        );
+        let long_ago = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(86400);
+        document.set_modified(long_ago).unwrap();
+        assert_eq!(document.metadata().unwrap().modified, Some(long_ago));
+        document.touch().unwrap();
+        assert!(document.metadata().unwrap().modified.unwrap() > long_ago);
+        document.set_readonly(true).unwrap();
+        assert!(document.metadata().unwrap().readonly);
+        document.set_readonly(false).unwrap();
+        #[cfg(unix)]
+        {
+            document.set_mode(0o600).unwrap();
+            assert_eq!(document.metadata().unwrap().mode.unwrap() & 0o777, 0o600);
+            let link = folder.join("link.txt");
+            std::os::unix::fs::symlink(&pathbuf, &link).unwrap();
+            let link_metadata = link.metadata().unwrap();
+            assert!(link_metadata.is_symlink);
+            assert_eq!(link_metadata.kind, EntryKind::File);
+            assert!(link_metadata.same_file_as(&document.metadata().unwrap()));
+            assert!(!link_metadata.same_file_as(&folder.metadata().unwrap()));
+        }
+    }
+    #[test]
+    /// Values round-trip through every enabled format, and parse errors carry their line and column.
+    fn load_and_save() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Settings {
+            theme: String,
+            font_size: u32,
+        }
+        let folder = scratch("load_and_save");
+        let settings = Settings {
+            theme: "dark".to_string(),
+            font_size: 12,
+        };
+        let document = |name: &str| {
+            Document::at_path(folder.join(name).display(), name, Create::OnlyIfNotExists).unwrap()
+        };
+        assert_eq!(
+            document("settings.ini").save(&settings).unwrap_err().kind(),
+            DocumentErrorKind::UnsupportedFormat
+        );
+        let formats = [
+            ("settings.json", "{\n  \"theme\": 12\n}", (2, 13)),
+            (
+                "settings.toml",
+                "theme = \"dark\"\nfont_size = \"big\"",
+                (2, 13),
+            ),
+            ("settings.yaml", "theme: dark\nfont_size: [big]", (2, 12)),
+            ("settings.ron", "(\n  theme: 12,\n)", (2, 10)),
+        ];
+        for (name, invalid, (line, column)) in formats {
+            let mut document = document(name);
+            let format = Format::from_extension(&document.extension()).unwrap();
+            let enabled = match format {
+                Format::Json => cfg!(feature = "json"),
+                Format::Toml => cfg!(feature = "toml"),
+                Format::Yaml => cfg!(feature = "yaml"),
+                Format::Ron => cfg!(feature = "ron"),
+            };
+            if !enabled {
+                let error = document.save(&settings).unwrap_err();
+                assert!(matches!(error, DocumentError::FormatNotEnabled(_, f) if f == format));
+                continue;
+            }
+            document.save(&settings).unwrap();
+            assert_eq!(document.load::<Settings>().unwrap(), settings);
+            document.replace_with(invalid.as_bytes()).unwrap();
+            match document.load::<Settings>() {
+                Err(DocumentError::CouldNotParseFile(_, error)) => {
+                    assert_eq!(
+                        (error.line, error.column),
+                        (Some(line), Some(column)),
+                        "{name}: {error}"
+                    )
+                }
+                other => panic!("{name}: expected a parse error, got {other:?}"),
+            }
+        }
+    }
+    #[test]
+    #[cfg(feature = "settings")]
+    /// Settings are seeded from Default, and older files are backed up and migrated to the current schema version.
+    fn settings_migration() {
+        #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Preferences {
+            theme: String,
+            font_size: u32,
+        }
+        let _roots = Sandbox::new().unwrap().enter();
+        let config = || Project(Config([]).with_id("com", "example", "Settings"));
+        let mut preferences = Settings::<Preferences>::load(config(), "preferences.json").unwrap();
+        assert_eq!(*preferences, Preferences::default());
+        let pathbuf = PathBuf::from(preferences.document().path());
+        assert!(std::fs::read_to_string(&pathbuf)
+            .unwrap()
+            .contains("\"schema_version\": 0"));
+        preferences.font_size = 12;
+        preferences.save().unwrap();
+        // Version 1 renamed "colour" to "theme".
+        let schema = || {
+            Schema::new(1).migration(0, |mut value| {
+                value["theme"] = value["colour"].take();
+                value.as_object_mut().unwrap().remove("colour");
+                Ok(value)
+            })
+        };
+        let old = "{\"colour\": \"dark\", \"font_size\": 12}";
+        std::fs::write(&pathbuf, old).unwrap();
+        let preferences =
+            Settings::<Preferences>::load_with_schema(config(), "preferences.json", schema())
+                .unwrap();
+        assert_eq!(preferences.theme, "dark");
+        assert_eq!(preferences.version(), 1);
+        assert_eq!(
+            std::fs::read_to_string(pathbuf.with_file_name("preferences.json.v0.bak")).unwrap(),
+            old
+        );
+        let reloaded =
+            Settings::<Preferences>::load_with_schema(config(), "preferences.json", schema())
+                .unwrap();
+        assert_eq!(reloaded.into_inner(), preferences.into_inner());
+        let error = Settings::<Preferences>::load(config(), "preferences.json").unwrap_err();
+        assert!(matches!(error, DocumentError::SchemaVersionTooNew(_, 1)));
+    }
+    #[test]
+    #[cfg(all(feature = "settings", feature = "toml"))]
+    /// Settings with optional values can be saved to TOML, which has no null.
+    fn toml_settings() {
+        #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Window {
+            title: String,
+            size: Option<(u32, u32)>,
+        }
+        #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Layout {
+            main: Window,
+            sidebar: Option<Window>,
+        }
+        let _roots = Sandbox::new().unwrap().enter();
+        let config = || Project(Config([]).with_id("com", "example", "Layout"));
+        let mut layout = Settings::<Layout>::load(config(), "layout.toml").unwrap();
+        layout.sidebar = Some(Window {
+            title: "Files".to_string(),
+            size: None,
+        });
+        layout.save().unwrap();
+        let reloaded = Settings::<Layout>::load(config(), "layout.toml").unwrap();
+        assert_eq!(*reloaded, *layout);
+        layout.main.size = Some((800, 600));
+        layout.sidebar = None;
+        layout.save().unwrap();
+        let reloaded = Settings::<Layout>::load(config(), "layout.toml").unwrap();
+        assert_eq!(reloaded.into_inner(), layout.into_inner());
+    }
+    #[test]
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    /// The config search path puts the user's config before the system-wide ones, and merged layers remember where each key came from.
+    fn layered_config() {
+        /// A user config folder and two system-wide ones, like `$XDG_CONFIG_HOME` and `$XDG_CONFIG_DIRS`.
+        #[derive(Debug)]
+        struct Layers(PathBuf);
+        impl Roots for Layers {
+            fn folder(&self, _: &KnownFolder) -> Result<PathBuf, DocumentError> {
+                Ok(self.0.join("home").join("layers"))
+            }
+            fn system_folders(&self, _: &KnownFolder) -> Result<Vec<PathBuf>, DocumentError> {
+                Ok(vec![
+                    self.0.join("site").join("layers"),
+                    self.0.join("vendor").join("layers"),
+                ])
+            }
+        }
+        let folder = scratch("layered_config");
+        let (home, site, vendor) = (
+            folder.join("home"),
+            folder.join("site"),
+            folder.join("vendor"),
+        );
+        let _roots = Layers(folder.clone()).enter();
+        let search_path = SearchPath::new(Project(
+            Config(["profiles"]).with_id("com", "example", "Layers"),
+        ))
+        .unwrap();
+        let layers = [&home, &site, &vendor].map(|layer| layer.join("layers").join("profiles"));
+        assert_eq!(search_path.folders(), layers);
+        for (layer, content) in layers.iter().zip([
+            r#"{"plugins": ["b", "c"]}"#,
+            r#"{"appearance": {"theme": "dark"}}"#,
+            r#"{"appearance": {"theme": "light", "font": "Sans"}, "plugins": ["a"]}"#,
+        ]) {
+            std::fs::create_dir_all(layer).unwrap();
+            std::fs::write(layer.join("default.json"), content).unwrap();
+        }
+        std::fs::remove_file(layers[1].join("default.json")).unwrap();
+        let found: Vec<String> = search_path
+            .find("default.json")
+            .iter()
+            .map(|document| document.path())
+            .collect();
+        assert_eq!(
+            found,
+            [&layers[0], &layers[2]].map(|layer| layer.join("default.json").path())
+        );
+        #[cfg(feature = "settings")]
+        {
+            std::fs::write(
+                layers[1].join("default.json"),
+                r#"{"appearance": {"theme": "dark"}}"#,
+            )
+            .unwrap();
+            let merged = search_path
+                .load_merged::<serde_json::Value>("default.json")
+                .unwrap();
+            assert_eq!(
+                *merged,
+                serde_json::json!({"appearance": {"theme": "dark", "font": "Sans"}, "plugins": ["b", "c"]})
+            );
+            assert_eq!(
+                merged.source("appearance.theme"),
+                Some(layers[1].join("default.json").as_path())
+            );
+            assert_eq!(
+                merged.source("appearance.font"),
+                Some(layers[2].join("default.json").as_path())
+            );
+            assert_eq!(
+                merged.source("plugins"),
+                Some(layers[0].join("default.json").as_path())
+            );
+            assert_eq!(merged.source("appearance"), None);
+        }
+    }
+    #[test]
+    /// Entered Roots keep well-known folders in a sandbox, for a scope, a try_with_options call, or the whole program.
+    fn sandbox_roots() {
+        let sandbox = Sandbox::new().unwrap();
+        let home = sandbox.root().join("home");
+        {
+            let _roots = sandbox.clone().enter();
+            let notes =
+                Document::at(User(Documents([])), "notes.txt", Create::OnlyIfNotExists).unwrap();
+            assert_eq!(
+                PathBuf::from(notes.path()),
+                home.join("Documents/notes.txt")
+            );
+            let config = Project(Config(["profiles"]).with_id("com", "example", "My App"));
+            assert_eq!(
+                PathBuf::from(config.path()),
+                home.join(".config/myapp/profiles")
+            );
+            assert_eq!(
+                SearchPath::new(config).unwrap().folders(),
+                [
+                    home.join(".config/myapp/profiles"),
+                    sandbox.root().join("etc/xdg/myapp/profiles")
+                ]
+            );
+            let inner = Sandbox::new().unwrap();
+            let inner_root = inner.root().to_path_buf();
+            {
+                let _inner_roots = inner.enter();
+                assert!(PathBuf::from(User(Home([])).path()).starts_with(&inner_root));
+            }
+            assert!(!inner_root.exists());
+            assert_eq!(PathBuf::from(User(Home([])).path()), home);
+            let (first, second) = (Sandbox::new().unwrap(), Sandbox::new().unwrap());
+            let second_root = second.root().to_path_buf();
+            let first_roots = first.enter();
+            let second_roots = second.enter();
+            drop(first_roots);
+            assert!(PathBuf::from(User(Home([])).path()).starts_with(&second_root));
+            drop(second_roots);
+            assert_eq!(PathBuf::from(User(Home([])).path()), home);
+        }
+        assert_ne!(PathBuf::from(User(Home([])).path()), home);
+        try_with_options(
+            || {
+                [Document::at(
+                    User(Desktop([])),
+                    "todo.txt",
+                    Create::OnlyIfNotExists,
+                )]
+            },
+            WithOptions::new().roots(sandbox.clone()),
+            |mut d| {
+                d["todo.txt"].append(b"Buy milk")?;
+                assert!(PathBuf::from(User(Desktop([])).path()).starts_with(sandbox.root()));
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(home.join("Desktop/todo.txt")).unwrap(),
+            "Buy milk"
+        );
+        {
+            let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+            set_roots(sandbox.clone());
+            let on_other_thread = std::thread::spawn(|| User(Music([])).path())
+                .join()
+                .unwrap();
+            reset_roots();
+            assert_eq!(PathBuf::from(on_other_thread), home.join("Music"));
+        }
+        let root = sandbox.root().to_path_buf();
+        drop(sandbox);
+        assert!(!root.exists());
+    }
+    #[test]
+    /// Documents can be copied and moved between sandboxed folders with each Create policy.
+    fn copy_and_move() {
+        let _roots = Sandbox::new().unwrap().enter();
+        let mut draft = Document::at(User(Documents([])), "draft.txt", Create::OnlyIfNotExists)
+            .alias("draft")
+            .unwrap();
+        draft.replace_with(b"draft").unwrap();
+        assert_eq!(
+            draft
+                .copy_to(User(Desktop([])), Create::No)
+                .unwrap_err()
+                .kind(),
+            DocumentErrorKind::NotFound
+        );
+        let copy = draft
+            .copy_to(User(Desktop([])), Create::OnlyIfNotExists)
+            .unwrap();
+        assert_eq!(copy.alias, "draft");
+        assert_eq!(copy.content().unwrap(), "draft");
+        let second_copy = draft
+            .copy_to(User(Desktop([])), Create::AutoRenameIfExists)
+            .unwrap();
+        assert_eq!(second_copy.name(), "draft(1).txt");
+        for create in [Create::No, Create::OnlyIfNotExists] {
+            let error = draft.copy_to(User(Documents([])), create).unwrap_err();
+            assert!(matches!(error, DocumentError::CouldNotCopyFile(..)));
+            assert_eq!(draft.content().unwrap(), "draft");
+        }
+        #[cfg(unix)]
+        {
+            let link = PathBuf::from(User(Desktop(["Links"])).path());
+            std::fs::create_dir_all(&link).unwrap();
+            std::fs::hard_link(draft.path(), link.join("draft.txt")).unwrap();
+            assert!(draft.copy_to(User(Desktop(["Links"])), Create::No).is_err());
+            assert_eq!(draft.content().unwrap(), "draft");
+        }
+        draft
+            .move_to(User(Desktop(["Drafts"])), Create::OnlyIfNotExists)
+            .unwrap();
+        assert_eq!(draft.alias, "draft");
+        assert!(draft.path().ends_with("Desktop/Drafts/draft.txt"));
+        assert!(!Document::at(User(Documents([])), "draft.txt", Create::No).exists());
+        assert_eq!(draft.content().unwrap(), "draft");
+    }
+    #[test]
+    /// with(), DocumentMap, Create policies, staged writes and Walk work entirely in memory with the InMemory backend.
+    fn in_memory_backend() {
+        let _env = ENV.lock().unwrap_or_else(|error| error.into_inner());
+        let memory = InMemory::new();
+        let folder = PathBuf::from("/documents-in-memory/notes");
+        let path = |name: &str| folder.join(name).display().to_string();
+        let lines = try_with_options(
+            || {
+                [
+                    Document::at_path(path("monday.txt"), "monday.txt", Create::OnlyIfNotExists),
+                    Document::at_path(path("tuesday.txt"), "tuesday.txt", Create::OnlyIfNotExists),
+                ]
+            },
+            WithOptions::new().backend(memory.clone()),
+            |mut d| {
+                d["monday.txt"].append(b"Standup\n")?.append(b"Review\n")?;
+                d["tuesday.txt"].replace_atomically(b"Release")?;
+                Ok(d["monday.txt"].lines()?.count())
+            },
+        )
+        .unwrap();
+        assert_eq!(lines, 2);
+        assert!(!folder.as_path().exists());
+        assert!(memory.exists(&folder.join("tuesday.txt")));
+        assert_eq!(memory.read_dir(&folder).unwrap().len(), 2);
+        // A guard dropped out of order takes back its own Backend, not the one entered after it.
+        let other = InMemory::new();
+        let memory_guard = memory.clone().enter();
+        let other_guard = other.clone().enter();
+        drop(memory_guard);
+        Document::at_path(path("thursday.txt"), "thursday", Create::OnlyIfNotExists).unwrap();
+        assert!(other.exists(&folder.join("thursday.txt")));
+        assert!(!memory.exists(&folder.join("thursday.txt")));
+        drop(other_guard);
+        let _backend = memory.clone().enter();
+        let mut monday = Document::at_path(path("monday.txt"), "monday", Create::No).unwrap();
+        assert_eq!(monday.content().unwrap(), "Standup\nReview\n");
+        assert_eq!(monday.metadata().unwrap().size, 15);
+        let mut wednesday =
+            Document::at_path(path("wednesday.txt"), "wednesday", Create::OnlyIfNotExists).unwrap();
+        wednesday.append(b"A long agenda").unwrap();
+        wednesday.replace_with(b"Done").unwrap();
+        assert_eq!(wednesday.content().unwrap(), "Done");
+        let mut open = wednesday.open(Mode::ReadReplace).unwrap();
+        assert_eq!(open.content().unwrap(), "");
+        drop(open);
+        wednesday.delete().unwrap();
+        assert_eq!(
+            monday.file(Mode::Read).unwrap_err().kind(),
+            DocumentErrorKind::Other
+        );
+        assert_eq!(
+            Document::at_path(path("monday.txt"), "copy", Create::AutoRenameIfExists)
+                .unwrap()
+                .name(),
+            "monday(1).txt"
+        );
+        let result = try_with_options(
+            || {
+                [Document::at_path(
+                    path("tuesday.txt"),
+                    "tuesday",
+                    Create::No,
+                )]
+            },
+            WithOptions::new().stage_writes(true),
+            |mut d| {
+                d["tuesday"].append(b" notes")?;
+                Err("cancelled")?;
+                Ok(())
+            },
+        );
+        assert!(matches!(result, Err(WithError::Closure(_))));
+        assert_eq!(memory.read_dir(&folder).unwrap().len(), 3);
+        assert_eq!(
+            Document::at_path(path("tuesday.txt"), "tuesday", Create::No)
+                .unwrap()
+                .content()
+                .unwrap(),
+            "Release"
+        );
+        let _ = try_with(
+            [
+                Document::at(
+                    User(Home(["documents-in-memory"])),
+                    "a.txt",
+                    Create::OnlyIfNotExists,
+                ),
+                Document::at(
+                    User(Home(["documents-in-memory", "b"])),
+                    "c.txt",
+                    Create::OnlyIfNotExists,
+                ),
+            ],
+            |_| (),
+        );
+        let walked: Vec<_> = User(Home(["documents-in-memory"]))
+            .walk()
+            .sort_by(SortOrder::Name)
+            .map(|document| document.unwrap().alias)
+            .collect();
+        assert_eq!(walked, ["a.txt", "b/c.txt"]);
+        assert!(!std::path::Path::new(&User(Home(["documents-in-memory"])).path()).exists());
+        monday.delete().unwrap();
+        assert!(!monday.exists());
+    }
+    #[test]
+    /// Faults scripted with Faulty make writes, opens and creates fail, and crashes leave the original file intact.
+    fn fault_injection() {
+        use std::io::ErrorKind;
+        let folder = scratch("fault_injection");
+        let notes = folder.join("notes.txt");
+        std::fs::write(&notes, "old").unwrap();
+        let faulty = Faulty::new(LocalDisk)
+            .fail(
+                Fault::new(Operation::Write)
+                    .path("notes.txt")
+                    .nth(3)
+                    .error(ErrorKind::StorageFull),
+            )
+            .fail(
+                Fault::new(Operation::Open)
+                    .path("secret.txt")
+                    .error(ErrorKind::PermissionDenied),
+            )
+            .fail(Fault::new(Operation::Exists).path("race.txt"));
+        let _backend = faulty.clone().enter();
+        let mut document = Document::at_path(notes.display(), "notes", Create::No).unwrap();
+        document.append(b" one").unwrap().append(b" two").unwrap();
+        assert_eq!(
+            document.append(b" three").unwrap_err().kind(),
+            DocumentErrorKind::StorageFull
+        );
+        assert_eq!(document.content().unwrap(), "old one two");
+        std::fs::write(folder.join("secret.txt"), "hidden").unwrap();
+        let secret = Document::at_path(folder.join("secret.txt").display(), "secret", Create::No);
+        assert_eq!(
+            secret.unwrap().content().unwrap_err().kind(),
+            DocumentErrorKind::PermissionDenied
+        );
+        std::fs::write(folder.join("race.txt"), "created by someone else").unwrap();
+        assert_eq!(
+            Document::at_path(
+                folder.join("race.txt").display(),
+                "race",
+                Create::OnlyIfNotExists
+            )
+            .unwrap_err()
+            .kind(),
+            DocumentErrorKind::AlreadyExists
+        );
+        faulty.clear();
+        let faulty = faulty.fail(Fault::new(Operation::Write).partial(2).crash());
+        assert!(document.replace_atomically(b"new content").is_err());
+        assert!(faulty.crashed());
+        assert!(document.bytes().is_err());
+        faulty.recover();
+        assert_eq!(document.content().unwrap(), "old one two");
+        let leftovers: Vec<_> = std::fs::read_dir(&folder)
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        assert!(leftovers.contains(&"ne".to_string()));
+    }
+    #[test]
+    /// Shared locks exclude exclusive ones on disk and in memory, and the lock option of try_with_options locks every file until it returns.
+    fn advisory_locks() {
+        let folder = scratch("advisory_locks");
+        let log = Document::at_path(
+            folder.join("log.txt").display(),
+            "log",
+            Create::OnlyIfNotExists,
+        )
+        .unwrap();
+        let memory = InMemory::new();
+        let in_memory = {
+            let _backend = memory.clone().enter();
+            Document::at_path("/logs/log.txt", "log", Create::OnlyIfNotExists).unwrap()
+        };
+        for document in [&log, &in_memory] {
+            let exclusive = document.lock_exclusive().unwrap();
+            assert_eq!(exclusive.kind(), LockKind::Exclusive);
+            assert!(document.try_lock_shared().unwrap().is_none());
+            drop(exclusive);
+            let shared = document.try_lock_shared().unwrap().unwrap();
+            let _also_shared = document.lock_shared().unwrap();
+            assert!(document.try_lock_exclusive().unwrap().is_none());
+            drop(shared);
+            assert!(document.try_lock_exclusive().unwrap().is_none());
+        }
+        let waiting = {
+            let in_memory = in_memory.clone();
+            let exclusive = in_memory.lock_exclusive().unwrap();
+            let waiting = std::thread::spawn(move || in_memory.lock_shared().map(|_| ()));
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            assert!(!waiting.is_finished());
+            drop(exclusive);
+            waiting
+        };
+        waiting.join().unwrap().unwrap();
+        let todo = folder.join("todo.txt");
+        try_with_options(
+            || {
+                [
+                    Document::at_path(todo.display(), "todo", Create::OnlyIfNotExists),
+                    Document::at_path(log.path(), "log", Create::No),
+                    Document::at_path(log.path(), "_", Create::No),
+                ]
+            },
+            WithOptions::new().lock(LockKind::Exclusive),
+            |mut d| {
+                assert!(log.try_lock_shared()?.is_none());
+                d["todo"].append(b"locked")?;
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert!(log.try_lock_exclusive().unwrap().is_some());
+        let _backend = Faulty::new(LocalDisk)
+            .fail(Fault::new(Operation::Lock).path("log.txt"))
+            .enter();
+        let result = try_with_options(
+            || {
+                [
+                    Document::at_path(
+                        folder.join("new.txt").display(),
+                        "new",
+                        Create::OnlyIfNotExists,
+                    ),
+                    Document::at_path(log.path(), "log", Create::No),
+                ]
+            },
+            WithOptions::new().lock(LockKind::Shared),
+            |_| Some(()),
+        );
+        match result {
+            Err(WithError::Setup(errors)) => assert!(matches!(
+                errors.as_slice(),
+                [(1, DocumentError::CouldNotLockFile(_, _))]
+            )),
+            _ => panic!("the lock should have failed"),
+        }
+        assert!(!folder.join("new.txt").as_path().exists());
+        let result = try_with_options(
+            || -> [Result<Document, DocumentError>; 0] {
+                panic!("documents should not be created")
+            },
+            WithOptions::new()
+                .lock(LockKind::Exclusive)
+                .stage_writes(true),
+            |_| (),
+        );
+        assert!(matches!(result, Err(WithError::InvalidOptions(_))));
+    }
+    #[test]
+    /// An OpenDocument reads, writes and seeks through one open file, and enforces its Mode until it is closed.
+    fn open_document() {
+        use std::io::{Read, Seek, SeekFrom, Write};
+        let folder = scratch("open_document");
+        let document = Document::at_path(
+            folder.join("records.txt").display(),
+            "records",
+            Create::OnlyIfNotExists,
+        )
+        .unwrap();
+        let mut file = document.open(Mode::ReadReplace).unwrap();
+        file.write_at(0, b"0000").unwrap();
+        for _ in 0..3 {
+            file.append(b"|record").unwrap();
+        }
+        file.write_at(0, b"0003").unwrap();
+        file.write_all(b"|RECORD").unwrap();
+        assert_eq!(file.read_at(0, 4).unwrap(), b"0003");
+        assert_eq!(file.seek(SeekFrom::End(-7)).unwrap(), 18);
+        let mut record = String::new();
+        file.read_to_string(&mut record).unwrap();
+        assert_eq!(record, "|record");
+        file.close().unwrap();
+        assert!(!file.is_open());
+        assert!(matches!(
+            file.read_at(0, 4),
+            Err(DocumentError::FileNotOpen(_))
+        ));
+        assert!(matches!(file.close(), Err(DocumentError::FileNotOpen(_))));
+        assert_eq!(document.content().unwrap(), "0003|RECORD|record|record");
+        let mut reader = document.open(Mode::Read).unwrap();
+        assert!(reader.content().unwrap().starts_with("0003"));
+        let error = reader.append(b"nope").unwrap_err();
+        assert!(matches!(error, DocumentError::FileNotWritable(_)));
+        assert_eq!(error.kind(), DocumentErrorKind::WrongMode);
+        assert_eq!(
+            reader.write(b"nope").unwrap_err().kind(),
+            std::io::ErrorKind::PermissionDenied
+        );
+        let mut appender = document.open(Mode::Append).unwrap();
+        assert!(matches!(
+            appender.read_at(0, 4),
+            Err(DocumentError::FileNotReadable(_))
+        ));
+        assert!(matches!(
+            appender.write_at(0, b"nope"),
+            Err(DocumentError::FileNotWritable(_))
+        ));
+        appender.append(b"|last").unwrap();
+        drop(appender);
+        assert!(document.content().unwrap().ends_with("|last"));
+    }
+    #[test]
+    /// Read-only Documents read like any other, their copies can be written, and DocumentMaps hand out read-only views.
+    fn read_only_documents() {
+        let _roots = Sandbox::new().unwrap().enter();
+        Document::at(User(Documents([])), "template.txt", Create::OnlyIfNotExists)
+            .unwrap()
+            .replace_with(b"Dear reader")
+            .unwrap();
+        let template = Document::at(User(Documents([])), "template.txt", Create::No)
+            .read_only()
+            .unwrap();
+        assert_eq!(template.content().unwrap(), "Dear reader");
+        assert_eq!(template.bytes_range(5, 6).unwrap(), b"reader");
+        assert!(template.exists());
+        let mut copy = template
+            .copy_to(User(Desktop([])), Create::OnlyIfNotExists)
+            .unwrap();
+        copy.append(b", hello").unwrap();
+        assert_eq!(copy.content().unwrap(), "Dear reader, hello");
+        assert_eq!(template.content().unwrap(), "Dear reader");
+        assert_eq!(
+            template.open(Mode::Append).unwrap_err().kind(),
+            DocumentErrorKind::WrongMode
+        );
+        let mut file = template.open(Mode::Read).unwrap();
+        assert_eq!(file.read_at(0, 4).unwrap(), b"Dear");
+        let letter = try_with(
+            [
+                Document::at(User(Documents([])), "template.txt", Create::No),
+                Document::at(User(Documents([])), "letter.txt", Create::OnlyIfNotExists),
+            ],
+            |mut d| {
+                let template: Document<ReadOnly> = d.read_only("template.txt");
+                assert!(d.contains("template.txt"));
+                d["letter.txt"].replace_with(template.content()?.as_bytes())?;
+                let letter = d.read_only("letter.txt");
+                d["letter.txt"].append(b", hello")?;
+                assert_eq!(letter.content()?, "Dear reader, hello");
+                d["letter.txt"].replace_with(b"Dear reader")?;
+                Ok::<_, Box<dyn std::error::Error>>(d["letter.txt"].content()?)
+            },
+        )
+        .unwrap();
+        assert_eq!(letter, "Dear reader");
+    }
+    #[test]
+    /// DocumentMaps can be looked up, changed, iterated and filtered without consuming them, and bad indexes list the valid aliases.
+    fn document_map() {
+        let _roots = Sandbox::new().unwrap().enter();
+        let notes = ["monday.txt", "tuesday.TXT", "todo.md"]
+            .map(|name| Document::at(User(Documents([])), name, Create::OnlyIfNotExists));
+        let mut d = try_with(notes, Some).unwrap();
+        assert_eq!(d.len(), 3);
+        assert!(d.contains("todo.md"));
+        assert!(d.get("wednesday.txt").is_none());
+        assert_eq!(d.aliases(), ["monday.txt", "todo.md", "tuesday.TXT"]);
+        for document in d.filter_mut(|document| document.name().starts_with('t')) {
+            document.append(b"t").unwrap();
+        }
+        let mut texts: Vec<_> = d
+            .with_extension(".txt")
+            .map(|document| document.name())
+            .collect();
+        texts.sort();
+        assert_eq!(texts, ["monday.txt", "tuesday.TXT"]);
+        for (alias, document) in &mut d {
+            document.append(alias.as_bytes()).unwrap();
+        }
+        assert_eq!(d.iter().count(), 3);
+        assert_eq!(d["todo.md"].content().unwrap(), "ttodo.md");
+        let todo = d.remove("todo.md").unwrap();
+        assert!(!d.contains("todo.md"));
+        assert!(d.insert(todo.clone()).is_none());
+        assert_eq!(d.insert(todo).unwrap().name(), "todo.md");
+        d.get_mut("monday.txt")
+            .unwrap()
+            .replace_with(b"empty")
+            .unwrap();
+        let message =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| d["wednesday.txt"].name()))
+                .unwrap_err()
+                .downcast::<String>()
+                .unwrap();
+        assert_eq!(
+            *message,
+            "No document with the alias \"wednesday.txt\": the aliases are \"monday.txt\", \"todo.md\", \"tuesday.TXT\""
+        );
+    }
+    #[tokio::test]
+    #[cfg(feature = "async")]
+    /// Async setup, reads, writes and line streaming work in with_async, which rolls back the Create policies if its future fails,
+    /// and try_with_async_options creates its Documents in the given roots, stages their writes and rolls back when its future panics.
+    async fn async_documents() {
+        let folder = scratch("async_documents");
+        let log = folder.join("logs").join("log.txt");
+        let lines = try_with_async(
+            [Document::at_path_async(log.display(), "log", Create::OnlyIfNotExists).await],
+            |mut d| async move {
+                for number in 0..100 {
+                    d["log"]
+                        .append_async(format!("{number}\n").as_bytes())
+                        .await?;
+                }
+                let mut lines = d["log"].lines_async().await?;
+                let mut count = 0;
+                while let Some(line) = lines.next_line().await? {
+                    assert_eq!(line, count.to_string());
+                    count += 1;
+                }
+                Ok::<_, Box<dyn std::error::Error>>(count)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(lines, 100);
+        let _roots = Sandbox::new().unwrap().enter();
+        let mut summary =
+            Document::at_async(User(Documents([])), "summary.txt", Create::OnlyIfNotExists)
+                .await
+                .unwrap();
+        summary
+            .replace_atomically_async(b"100 lines")
+            .await
+            .unwrap();
+        assert_eq!(summary.bytes_async().await.unwrap(), b"100 lines");
+        let result = try_with_async(
+            [Document::at_path_async(
+                folder.join("drafts").join("draft.txt").display(),
+                "draft",
+                Create::OnlyIfNotExists,
+            )
+            .await],
+            |mut d| async move {
+                d["draft"].replace_with_async(b"draft").await?;
+                assert_eq!(d["draft"].content_async().await?, "draft");
+                Err("cancelled")?;
+                Ok(())
+            },
+        )
+        .await;
+        assert!(matches!(result, Err(WithError::Closure(_))));
+        assert!(!folder.join("drafts").as_path().exists());
+        let sandbox = Sandbox::new().unwrap();
+        let report = sandbox.root().join("home/Documents/report.txt");
+        let options = WithOptions::new().roots(sandbox.clone()).stage_writes(true);
+        let make_report = || {
+            [Document::at(
+                User(Documents([])),
+                "report.txt",
+                Create::OnlyIfNotExists,
+            )]
+        };
+        let result = try_with_async_options(make_report, options.clone(), |mut d| async move {
+            d["report.txt"].append_async(b"draft").await?;
+            Err("cancelled")?;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(result, Err(WithError::Closure(_))));
+        assert!(!report.as_path().exists());
+        // A panicking future is rolled back before the panic is resumed.
+        let runtime = tokio::runtime::Handle::current();
+        let panicking_options = options.clone();
+        let panicked = tokio::task::spawn_blocking(move || {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                runtime.block_on(try_with_async_options(
+                    make_report,
+                    panicking_options,
+                    |mut d| async move {
+                        d["report.txt"].append_async(b"draft").await.unwrap();
+                        if d.contains("report.txt") {
+                            panic!("future panicked");
+                        }
+                        Some(())
+                    },
+                ))
+            }))
+            .is_err()
+        })
+        .await
+        .unwrap();
+        assert!(panicked);
+        assert!(!report.as_path().exists());
+        try_with_async_options(make_report, options, |mut d| async move {
+            d["report.txt"].append_async(b"final").await?;
+            Ok(())
+        })
+        .await
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&report).unwrap(), "final");
+    }
 }
@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+
+use crate::FileHandle;
+
+/// The kind of an advisory lock on a file.
+///
+/// *Shared*: any number of shared locks can be held on a file at once, e.g. by processes which only read it.
+///
+/// *Exclusive*: only one lock can be held on the file, e.g. by the process which writes it.
+///
+/// Take one with [`Document::lock_shared`](crate::Document::lock_shared), [`Document::lock_exclusive`](crate::Document::lock_exclusive),
+/// or for all Documents of a [`try_with_options`](crate::try_with_options) with [`WithOptions::lock`](crate::WithOptions::lock).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockKind {
+    Shared,
+    Exclusive,
+}
+
+/// An advisory lock on the file of a [`Document`](crate::Document), which is held until this guard is dropped.
+///
+/// Locks are advisory: they only keep out other processes and threads which take locks on the same file too. They are taken with `flock`
+/// on unix and `LockFileEx` on Windows, so other processes which use the same calls (including other programs using this library) respect them.
+///
+/// Note: the lock is on the file itself, not on its path. [`replace_atomically`](crate::Document::replace_atomically) and
+/// [`staged writes`](crate::WithOptions::stage_writes) put a new file at the path, which other processes can lock while this lock is still held.
+/// Use [`append`](crate::Document::append) or [`replace_with`](crate::Document::replace_with) on files shared with other processes.
+///
+/// ```ignore
+/// let mut log = Document::at(Project(Data([]).with_id("com", "example", "App")), "log.txt", Create::OnlyIfNotExists)?;
+/// {
+///     let _lock = log.lock_exclusive()?;
+///     log.append(b"Started\n")?;
+/// } // The lock is released here
+/// ```
+#[derive(Debug)]
+pub struct DocumentLock {
+    file: Box<dyn FileHandle>,
+    kind: LockKind,
+}
+
+impl DocumentLock {
+    pub(crate) fn new(file: Box<dyn FileHandle>, kind: LockKind) -> Self {
+        Self { file, kind }
+    }
+
+    /// Whether this lock is shared or exclusive.
+    pub fn kind(&self) -> LockKind {
+        self.kind
+    }
+}
+
+impl Drop for DocumentLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock too, so a failure here leaves nothing behind.
+        let _ = self.file.unlock();
+    }
+}
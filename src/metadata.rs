@@ -0,0 +1,69 @@
+use std::{path::Path, time::SystemTime};
+
+use crate::{backend::current_backend, DocumentError, FileSystemEntity};
+
+/// What kind of entry a path points to. Symbolic links are followed, see [`Metadata::is_symlink`](Metadata::is_symlink).
+///
+/// *File*: a regular file.
+///
+/// *Folder*: a folder (directory).
+///
+/// *Other*: anything else, such as a device, socket or pipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    File,
+    Folder,
+    Other,
+}
+
+/// Information about a file or folder, provided by [`FileSystemEntity::metadata`](crate::FileSystemEntity::metadata).
+///
+/// Symbolic links are followed, so the information is about the file or folder the link points to, except for *is_symlink*.
+///
+/// *kind*: whether this is a file, a folder or something else. See [`EntryKind`](EntryKind).
+///
+/// *size*: the size in bytes.
+///
+/// *created*, *modified*, *accessed*: when the file was created, last modified and last accessed. None if the system does not record it.
+///
+/// *readonly*: whether the file cannot be written to.
+///
+/// *mode*: the unix permission bits, e.g. `0o644`. None on other systems.
+///
+/// *is_symlink*: whether the path itself is a symbolic link.
+///
+/// *device*, *inode*: the identity of the file on the system. Two paths with the same device and inode are the same file,
+/// see [`same_file_as`](Metadata::same_file_as). None on systems other than unix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Metadata {
+    pub kind: EntryKind,
+    pub size: u64,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub readonly: bool,
+    pub mode: Option<u32>,
+    pub is_symlink: bool,
+    pub device: Option<u64>,
+    pub inode: Option<u64>,
+}
+
+impl Metadata {
+    /// Read the metadata of the file or folder at `pathbuf` from the current [`Backend`](crate::Backend).
+    pub(crate) fn of(pathbuf: &Path) -> Result<Self, DocumentError> {
+        match current_backend().metadata(pathbuf) {
+            Ok(metadata) => Ok(metadata),
+            Err(error) => Err(DocumentError::CouldNotReadMetadata(
+                pathbuf.to_path_buf().path(),
+                error,
+            )),
+        }
+    }
+
+    /// Whether this metadata and `other` belong to the same file, e.g. through a hard link or a symbolic link.
+    /// Always false on systems other than unix.
+    pub fn same_file_as(&self, other: &Metadata) -> bool {
+        self.inode.is_some() && self.device == other.device && self.inode == other.inode
+    }
+}
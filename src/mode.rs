@@ -13,6 +13,10 @@
 /// Read, ReadReplace, ReadAppend are `read`-able.
 ///
 /// Replace, Append, ReadReplace, ReadAppend are `write`-able.
+///
+/// Note: opening a file with Replace or ReadReplace empties it straight away, including with [`Document::file`](crate::Document::file)
+/// and [`Document::open`](crate::Document::open). In 0.1.2 and earlier, the old content was kept until it was written over,
+/// so a file opened with ReadReplace could still be read; open it with ReadAppend for that instead.
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum Mode {
     #[default]
@@ -24,42 +28,30 @@ pub enum Mode {
 }
 
 impl Mode {
-    /// ```
-    /// match self {
-    ///     Self::Read | Self::ReadReplace | Self::ReadAppend => true,
-    ///     _ => false,
-    /// }
+    /// ```ignore
+    /// matches!(self, Self::Read | Self::ReadReplace | Self::ReadAppend)
     /// ```
     pub fn readable(&self) -> bool {
-        match self {
-            Self::Read | Self::ReadReplace | Self::ReadAppend => true,
-            _ => false,
-        }
+        matches!(self, Self::Read | Self::ReadReplace | Self::ReadAppend)
     }
 
-    /// ```
-    /// match self {
-    ///     Self::Replace | Self::Append | Self::ReadAppend | Self::ReadReplace => true,
-    ///     _ => false,
-    /// }
+    /// ```ignore
+    /// matches!(
+    ///     self,
+    ///     Self::Replace | Self::Append | Self::ReadAppend | Self::ReadReplace
+    /// )
     /// ```
     pub fn writable(&self) -> bool {
-        match self {
-            Self::Replace | Self::Append | Self::ReadAppend | Self::ReadReplace => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            Self::Replace | Self::Append | Self::ReadAppend | Self::ReadReplace
+        )
     }
 
-    /// ```
-    /// match self {
-    ///     Self::Append | Self::ReadAppend => true,
-    ///     _ => false,
-    /// }
+    /// ```ignore
+    /// matches!(self, Self::Append | Self::ReadAppend)
     /// ```
     pub fn appendable(&self) -> bool {
-        match self {
-            Self::Append | Self::ReadAppend => true,
-            _ => false,
-        }
+        matches!(self, Self::Append | Self::ReadAppend)
     }
 }
@@ -0,0 +1,216 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::{DocumentError, Durability, FileHandle, Mode};
+
+/// A file kept open by [`Document::open`](crate::Document::open), for many reads and writes without opening the file again for each of them.
+///
+/// OpenDocument implements [`Read`](std::io::Read), [`Write`](std::io::Write) and [`Seek`](std::io::Seek) like a [`File`](std::fs::File),
+/// so it can be handed to other libraries, and reads and writes continue from the current position.
+/// [`read_at`](OpenDocument::read_at) and [`write_at`](OpenDocument::write_at) go to a given position instead.
+///
+/// The [`Mode`](Mode) it was opened with is enforced: reading fails with [`DocumentError::FileNotReadable`](DocumentError::FileNotReadable)
+/// unless the Mode is readable, and writing fails with [`DocumentError::FileNotWritable`](DocumentError::FileNotWritable) unless it is writable.
+/// Every operation fails with `FileNotOpen` after [`close`](OpenDocument::close).
+///
+/// Writes are only pushed to the [`Durability`](Durability) of the Document by [`close`](OpenDocument::close) and [`sync`](OpenDocument::sync),
+/// not after every write. Dropping an OpenDocument closes it too, but ignores any error.
+///
+/// ```ignore
+/// let mut log = document.open(Mode::ReadAppend)?;
+/// for number in 0..1000 {
+///     log.append(format!("{number}\n").as_bytes())?;
+/// }
+/// let first_line = log.read_at(0, 2)?;
+/// log.close()?;
+/// ```
+#[derive(Debug)]
+pub struct OpenDocument {
+    /// None once closed.
+    file: Option<Box<dyn FileHandle>>,
+    mode: Mode,
+    path: String,
+    durability: Durability,
+}
+
+impl OpenDocument {
+    pub(crate) fn new(
+        file: Box<dyn FileHandle>,
+        mode: Mode,
+        path: String,
+        durability: Durability,
+    ) -> Self {
+        Self {
+            file: Some(file),
+            mode,
+            path,
+            durability,
+        }
+    }
+
+    /// The [`Mode`](Mode) this file was opened with.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Whether this file is still open, i.e. [`close`](OpenDocument::close) has not been called.
+    pub fn is_open(&self) -> bool {
+        self.file.is_some()
+    }
+
+    fn readable_file(&mut self) -> Result<&mut Box<dyn FileHandle>, DocumentError> {
+        match &mut self.file {
+            Some(file) if self.mode.readable() => Ok(file),
+            Some(_) => Err(DocumentError::FileNotReadable(self.path.clone())),
+            None => Err(DocumentError::FileNotOpen(self.path.clone())),
+        }
+    }
+
+    fn writable_file(&mut self) -> Result<&mut Box<dyn FileHandle>, DocumentError> {
+        match &mut self.file {
+            Some(file) if self.mode.writable() => Ok(file),
+            Some(_) => Err(DocumentError::FileNotWritable(self.path.clone())),
+            None => Err(DocumentError::FileNotOpen(self.path.clone())),
+        }
+    }
+
+    /// Returns up to `len` bytes of the file, starting `offset` bytes from the start of the file. The current position is moved to the end of the bytes read.
+    ///
+    /// Fewer than `len` bytes are returned if the end of the file is reached first.
+    ///
+    /// Returns an error if the file is not open for reading or could not be read.
+    pub fn read_at(&mut self, offset: u64, len: usize) -> Result<Vec<u8>, DocumentError> {
+        let file = self.readable_file()?;
+        let mut bytes = Vec::with_capacity(len);
+        match file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.take(len as u64).read_to_end(&mut bytes))
+        {
+            Ok(_) => Ok(bytes),
+            Err(error) => Err(DocumentError::CouldNotReadFile(self.path.clone(), error)),
+        }
+    }
+
+    /// Write `content` over the file starting `offset` bytes from the start of the file, extending the file if needed.
+    /// The current position is moved to the end of the bytes written.
+    ///
+    /// Returns an error if the file is not open for writing, is open with an appending [`Mode`](Mode) (which only writes at the end of the file),
+    /// or the write operation fails.
+    pub fn write_at(&mut self, offset: u64, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        if self.file.is_some() && self.mode.appendable() {
+            Err(DocumentError::FileNotWritable(self.path.clone()))?
+        }
+        let file = self.writable_file()?;
+        match file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.write_all(content))
+        {
+            Ok(_) => Ok(self),
+            Err(error) => Err(DocumentError::CouldNotWriteFile(self.path.clone(), error)),
+        }
+    }
+
+    /// Add `content` to the end of the file. The current position is moved to the new end of the file.
+    ///
+    /// Returns an error if the file is not open for writing or the write operation fails.
+    pub fn append(&mut self, content: &[u8]) -> Result<&mut Self, DocumentError> {
+        let file = self.writable_file()?;
+        match file
+            .seek(SeekFrom::End(0))
+            .and_then(|_| file.write_all(content))
+        {
+            Ok(_) => Ok(self),
+            Err(error) => Err(DocumentError::CouldNotWriteFile(self.path.clone(), error)),
+        }
+    }
+
+    /// Returns the contents of the whole file, regardless of the current position. The current position is moved to the end of the file.
+    ///
+    /// Returns an error if the file is not open for reading, or its content could not be read or is not valid UTF-8.
+    pub fn content(&mut self) -> Result<String, DocumentError> {
+        let file = self.readable_file()?;
+        let mut content = String::new();
+        match file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| file.read_to_string(&mut content))
+        {
+            Ok(_) => Ok(content),
+            Err(error) => Err(DocumentError::CouldNotReadFile(self.path.clone(), error)),
+        }
+    }
+
+    /// Push everything written so far to the [`Durability`](Durability) of the Document this file was opened from.
+    ///
+    /// Returns an error if the file is not open, or the content could not be flushed.
+    pub fn sync(&mut self) -> Result<&mut Self, DocumentError> {
+        let durability = self.durability;
+        let Some(file) = &mut self.file else {
+            Err(DocumentError::FileNotOpen(self.path.clone()))?
+        };
+        let result = file.flush().and_then(|_| match durability {
+            Durability::Flush => Ok(()),
+            Durability::SyncData => file.sync_data(),
+            Durability::SyncAll => file.sync_all(),
+        });
+        match result {
+            Ok(_) => Ok(self),
+            Err(error) => Err(DocumentError::CouldNotWriteFile(self.path.clone(), error)),
+        }
+    }
+
+    /// Push everything written so far to the [`Durability`](Durability) of the Document, then close the file.
+    /// Every following operation fails with [`DocumentError::FileNotOpen`](DocumentError::FileNotOpen).
+    ///
+    /// The file is closed even if this returns an error. Returns an error if the file is already closed, or the content could not be flushed.
+    pub fn close(&mut self) -> Result<(), DocumentError> {
+        let result = if self.mode.writable() {
+            self.sync().map(|_| ())
+        } else if self.file.is_none() {
+            Err(DocumentError::FileNotOpen(self.path.clone()))
+        } else {
+            Ok(())
+        };
+        self.file = None;
+        result
+    }
+}
+
+/// Turn `FileNotOpen`, `FileNotReadable` or `FileNotWritable` into an [`io::Error`](std::io::Error) for the standard library's traits.
+fn io_error(error: DocumentError) -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, error)
+}
+
+impl Read for OpenDocument {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.readable_file().map_err(io_error)?.read(buf)
+    }
+}
+
+impl Write for OpenDocument {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writable_file().map_err(io_error)?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Err(io_error(DocumentError::FileNotOpen(self.path.clone()))),
+        }
+    }
+}
+
+impl Seek for OpenDocument {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        match &mut self.file {
+            Some(file) => file.seek(position),
+            None => Err(io_error(DocumentError::FileNotOpen(self.path.clone()))),
+        }
+    }
+}
+
+impl Drop for OpenDocument {
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            let _ = self.close();
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use std::{
+    cell::RefCell,
+    fmt::Debug,
+    fs::{create_dir_all, remove_dir_all},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use directories::UserDirs;
+
+use crate::{AppId, DocumentError, FileSystemEntity, KnownFolder};
+
+/// Where the well-known folders ([`KnownFolder`](KnownFolder)s) are on this device. Every [`Folder`](crate::Folder) and [`FolderPath`](crate::FolderPath)
+/// is resolved through the current Roots.
+///
+/// *SystemRoots*: the real folders of the user, used by default.
+///
+/// *Sandbox*: every folder inside one temporary folder, so that tests do not touch the user's files.
+///
+/// The current Roots are, from the most important to the least important: the Roots entered on this thread with [`enter`](Roots::enter)
+/// or [`WithOptions::roots`](crate::WithOptions::roots), the Roots set for the whole program with [`set_roots`](set_roots), and [`SystemRoots`](SystemRoots).
+///
+/// ```ignore
+/// #[test]
+/// fn saves_notes() {
+///     let sandbox = Sandbox::new().unwrap();
+///     let _roots = sandbox.clone().enter();
+///     // Created in the sandbox, not in the real Documents folder
+///     Document::at(User(Documents([])), "notes.txt", Create::OnlyIfNotExists).unwrap();
+///     assert!(sandbox.root().join("home/Documents/notes.txt").exists());
+/// }
+/// ```
+pub trait Roots: Debug + Send + Sync {
+    /// The path of `folder`.
+    ///
+    /// Returns an error if `folder` does not exist on this device.
+    fn folder(&self, folder: &KnownFolder) -> Result<PathBuf, DocumentError>;
+
+    /// The system-wide folders which correspond to `folder`, from the most important to the least important,
+    /// e.g. the folders in `$XDG_CONFIG_DIRS` for a config folder. Used by [`SearchPath`](crate::SearchPath). None by default.
+    fn system_folders(&self, folder: &KnownFolder) -> Result<Vec<PathBuf>, DocumentError> {
+        let _ = folder;
+        Ok(Vec::new())
+    }
+
+    /// The user's own trash folder, which [`Document::trash`](crate::Document::trash) and [`TrashItem`](crate::TrashItem) use for files on the same disk.
+    /// `$XDG_DATA_HOME/Trash` by default.
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    fn trash_folder(&self) -> Result<PathBuf, DocumentError> {
+        match directories::BaseDirs::new() {
+            Some(dirs) => Ok(dirs.data_dir().join("Trash")),
+            None => Err(DocumentError::UserDirsNotFound),
+        }
+    }
+
+    /// Use these Roots on this thread until the returned guard is dropped.
+    fn enter(self) -> RootsGuard
+    where
+        Self: Sized + 'static,
+    {
+        enter_roots(Arc::new(self))
+    }
+}
+
+static GLOBAL_ROOTS: RwLock<Option<Arc<dyn Roots>>> = RwLock::new(None);
+
+thread_local! {
+    /// The entered Rootss with the id of their guard, the current one last.
+    static ENTERED_ROOTS: RefCell<Vec<(usize, Arc<dyn Roots>)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Use `roots` for the whole program, on every thread which has not [`entered`](Roots::enter) its own Roots.
+pub fn set_roots(roots: impl Roots + 'static) {
+    let mut global_roots = GLOBAL_ROOTS
+        .write()
+        .unwrap_or_else(|error| error.into_inner());
+    *global_roots = Some(Arc::new(roots));
+}
+
+/// Go back to [`SystemRoots`](SystemRoots) for the whole program, undoing [`set_roots`](set_roots).
+pub fn reset_roots() {
+    let mut global_roots = GLOBAL_ROOTS
+        .write()
+        .unwrap_or_else(|error| error.into_inner());
+    *global_roots = None;
+}
+
+pub(crate) fn enter_roots(roots: Arc<dyn Roots>) -> RootsGuard {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    ENTERED_ROOTS.with(|entered| entered.borrow_mut().push((id, roots)));
+    RootsGuard {
+        id,
+        not_send: PhantomData,
+    }
+}
+
+/// Run `resolve` with the current [`Roots`](Roots).
+pub(crate) fn with_current_roots<T>(resolve: impl FnOnce(&dyn Roots) -> T) -> T {
+    let entered =
+        ENTERED_ROOTS.with(|entered| entered.borrow().last().map(|(_, roots)| roots.clone()));
+    if let Some(roots) = entered {
+        return resolve(roots.as_ref());
+    }
+    let global = GLOBAL_ROOTS
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone();
+    match global {
+        Some(roots) => resolve(roots.as_ref()),
+        None => resolve(&SystemRoots),
+    }
+}
+
+/// Keeps [`Roots`](Roots) entered on this thread with [`enter`](Roots::enter). The Roots entered before are used again when this guard is dropped.
+///
+/// Guards can be dropped in any order: each one only takes back its own Roots, and the most recently entered Roots which are still kept are used.
+#[derive(Debug)]
+#[must_use = "the Roots are only used until this guard is dropped"]
+pub struct RootsGuard {
+    id: usize,
+    not_send: PhantomData<*const ()>,
+}
+
+impl Drop for RootsGuard {
+    fn drop(&mut self) {
+        // Guards may be dropped in any order, so remove this guard's own entry rather than the last one.
+        ENTERED_ROOTS.with(|entered| entered.borrow_mut().retain(|(id, _)| *id != self.id));
+    }
+}
+
+/// The real folders of the user, as reported by the operating system. These are the default [`Roots`](Roots).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SystemRoots;
+
+fn user_folder(
+    folder: fn(&UserDirs) -> Option<&Path>,
+    not_found: DocumentError,
+) -> Result<PathBuf, DocumentError> {
+    match UserDirs::new() {
+        Some(dirs) => match folder(&dirs) {
+            Some(path) => Ok(path.to_path_buf()),
+            None => Err(not_found),
+        },
+        None => Err(DocumentError::UserDirsNotFound),
+    }
+}
+
+impl Roots for SystemRoots {
+    fn folder(&self, folder: &KnownFolder) -> Result<PathBuf, DocumentError> {
+        match folder {
+            KnownFolder::Documents => {
+                user_folder(UserDirs::document_dir, DocumentError::DocumentsDirNotFound)
+            }
+            KnownFolder::Pictures => {
+                user_folder(UserDirs::picture_dir, DocumentError::PicturesDirNotFound)
+            }
+            KnownFolder::Videos => {
+                user_folder(UserDirs::video_dir, DocumentError::VideosDirNotFound)
+            }
+            KnownFolder::Downloads => {
+                user_folder(UserDirs::download_dir, DocumentError::DownloadsDirNotFound)
+            }
+            KnownFolder::Desktop => {
+                user_folder(UserDirs::desktop_dir, DocumentError::DesktopDirNotFound)
+            }
+            KnownFolder::Music => user_folder(UserDirs::audio_dir, DocumentError::MusicDirNotFound),
+            KnownFolder::Public => {
+                user_folder(UserDirs::public_dir, DocumentError::PublicDirNotFound)
+            }
+            KnownFolder::Templates => {
+                user_folder(UserDirs::template_dir, DocumentError::TemplatesDirNotFound)
+            }
+            KnownFolder::Fonts => user_folder(UserDirs::font_dir, DocumentError::FontsDirNotFound),
+            KnownFolder::Home => user_folder(
+                |dirs| Some(dirs.home_dir()),
+                DocumentError::UserDirsNotFound,
+            ),
+            KnownFolder::Config(app_id) => Ok(app_id.project_dirs()?.config_dir().to_path_buf()),
+            KnownFolder::Data(app_id) => Ok(app_id.project_dirs()?.data_dir().to_path_buf()),
+            KnownFolder::DataLocal(app_id) => {
+                Ok(app_id.project_dirs()?.data_local_dir().to_path_buf())
+            }
+            KnownFolder::Cache(app_id) => Ok(app_id.project_dirs()?.cache_dir().to_path_buf()),
+            KnownFolder::State(app_id) => match app_id.project_dirs()?.state_dir() {
+                Some(path) => Ok(path.to_path_buf()),
+                None => Err(DocumentError::StateDirNotFound),
+            },
+            KnownFolder::Runtime(app_id) => match app_id.project_dirs()?.runtime_dir() {
+                Some(path) => Ok(path.to_path_buf()),
+                None => Err(DocumentError::RuntimeDirNotFound),
+            },
+            KnownFolder::Preference(app_id) => {
+                Ok(app_id.project_dirs()?.preference_dir().to_path_buf())
+            }
+        }
+    }
+
+    /// On Linux, the folders in `$XDG_CONFIG_DIRS` (`/etc/xdg` if not set) for *Config*,
+    /// and in `$XDG_DATA_DIRS` (`/usr/local/share` and `/usr/share` if not set) for *Data* and *DataLocal*.
+    fn system_folders(&self, folder: &KnownFolder) -> Result<Vec<PathBuf>, DocumentError> {
+        let mut folders = Vec::new();
+        #[cfg(all(unix, not(target_vendor = "apple")))]
+        {
+            let (app_id, variable, default) = match folder {
+                KnownFolder::Config(app_id) => (app_id, "XDG_CONFIG_DIRS", "/etc/xdg"),
+                KnownFolder::Data(app_id) | KnownFolder::DataLocal(app_id) => {
+                    (app_id, "XDG_DATA_DIRS", "/usr/local/share/:/usr/share/")
+                }
+                _ => return Ok(folders),
+            };
+            let project_path = app_id.project_dirs()?.project_path().to_path_buf();
+            let system_folders = match std::env::var_os(variable) {
+                Some(value) if !value.is_empty() => value,
+                _ => default.into(),
+            };
+            for system_folder in std::env::split_paths(&system_folders) {
+                // Relative paths in these variables are invalid and must be ignored.
+                if system_folder.is_absolute() {
+                    folders.push(system_folder.join(&project_path));
+                }
+            }
+        }
+        #[cfg(not(all(unix, not(target_vendor = "apple"))))]
+        let _ = folder;
+        Ok(folders)
+    }
+}
+
+/// [`Roots`](Roots) which put every well-known folder inside one folder, laid out like the home folder of a new Linux user:
+/// e.g. `User(Documents([]))` is "(root)/home/Documents" and `Project(Config([]))` is "(root)/home/.config/(application)".
+/// System-wide config and data folders are "(root)/etc/xdg/(application)" and "(root)/usr/share/(application)",
+/// and the trash folder is "(root)/home/.local/share/Trash".
+///
+/// The user folders are created with the Sandbox. Clones of a Sandbox share the same folder.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    folder: Arc<SandboxFolder>,
+}
+
+#[derive(Debug)]
+struct SandboxFolder {
+    root: PathBuf,
+    remove_on_drop: bool,
+}
+
+impl Drop for SandboxFolder {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            let _ = remove_dir_all(&self.root);
+        }
+    }
+}
+
+const SANDBOX_USER_FOLDERS: [&str; 9] = [
+    "Documents",
+    "Pictures",
+    "Videos",
+    "Downloads",
+    "Desktop",
+    "Music",
+    "Public",
+    "Templates",
+    ".local/share/fonts",
+];
+
+impl Sandbox {
+    /// A Sandbox in a new temporary folder, which is removed when the Sandbox and all of its clones are dropped.
+    ///
+    /// Returns an error if the temporary folder could not be created.
+    pub fn new() -> Result<Self, DocumentError> {
+        static SANDBOX_COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "documents-sandbox-{}-{}",
+            process::id(),
+            SANDBOX_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = remove_dir_all(&root);
+        Self::create(root, true)
+    }
+
+    /// A Sandbox in `root`, which is kept when the Sandbox is dropped.
+    ///
+    /// Returns an error if the folders could not be created.
+    pub fn at(root: impl Into<PathBuf>) -> Result<Self, DocumentError> {
+        Self::create(root.into(), false)
+    }
+
+    fn create(root: PathBuf, remove_on_drop: bool) -> Result<Self, DocumentError> {
+        let home = root.join("home");
+        for user_folder in SANDBOX_USER_FOLDERS {
+            let pathbuf = home.join(user_folder);
+            if let Err(error) = create_dir_all(&pathbuf) {
+                Err(DocumentError::CouldNotCreateParentFolder(
+                    pathbuf.path(),
+                    error,
+                ))?
+            }
+        }
+        Ok(Self {
+            folder: Arc::new(SandboxFolder {
+                root,
+                remove_on_drop,
+            }),
+        })
+    }
+
+    /// The folder which contains all well-known folders of this Sandbox.
+    pub fn root(&self) -> &Path {
+        &self.folder.root
+    }
+}
+
+/// The name of the folder of an app inside project folders, the same as on Linux.
+fn project_path(app_id: &AppId) -> String {
+    app_id
+        .application
+        .split_whitespace()
+        .collect::<String>()
+        .to_lowercase()
+}
+
+impl Roots for Sandbox {
+    fn folder(&self, folder: &KnownFolder) -> Result<PathBuf, DocumentError> {
+        let home = self.root().join("home");
+        let pathbuf = match folder {
+            KnownFolder::Home => home,
+            KnownFolder::Documents => home.join("Documents"),
+            KnownFolder::Pictures => home.join("Pictures"),
+            KnownFolder::Videos => home.join("Videos"),
+            KnownFolder::Downloads => home.join("Downloads"),
+            KnownFolder::Desktop => home.join("Desktop"),
+            KnownFolder::Music => home.join("Music"),
+            KnownFolder::Public => home.join("Public"),
+            KnownFolder::Templates => home.join("Templates"),
+            KnownFolder::Fonts => home.join(".local/share/fonts"),
+            KnownFolder::Config(app_id) | KnownFolder::Preference(app_id) => {
+                home.join(".config").join(project_path(app_id))
+            }
+            KnownFolder::Data(app_id) | KnownFolder::DataLocal(app_id) => {
+                home.join(".local/share").join(project_path(app_id))
+            }
+            KnownFolder::Cache(app_id) => home.join(".cache").join(project_path(app_id)),
+            KnownFolder::State(app_id) => home.join(".local/state").join(project_path(app_id)),
+            KnownFolder::Runtime(app_id) => self.root().join("run").join(project_path(app_id)),
+        };
+        Ok(pathbuf)
+    }
+
+    fn system_folders(&self, folder: &KnownFolder) -> Result<Vec<PathBuf>, DocumentError> {
+        let folders = match folder {
+            KnownFolder::Config(app_id) => {
+                vec![self.root().join("etc/xdg").join(project_path(app_id))]
+            }
+            KnownFolder::Data(app_id) | KnownFolder::DataLocal(app_id) => {
+                vec![self.root().join("usr/share").join(project_path(app_id))]
+            }
+            _ => Vec::new(),
+        };
+        Ok(folders)
+    }
+
+    #[cfg(all(unix, not(target_vendor = "apple")))]
+    fn trash_folder(&self) -> Result<PathBuf, DocumentError> {
+        Ok(self.root().join("home/.local/share/Trash"))
+    }
+}
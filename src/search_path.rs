@@ -0,0 +1,238 @@
+#[cfg(feature = "settings")]
+use std::{collections::BTreeMap, ops::Deref, path::Path};
+use std::{fmt::Display, path::PathBuf};
+
+#[cfg(feature = "settings")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "settings")]
+use serde_json::{Map, Value};
+
+use crate::{
+    backend::current_backend, roots::with_current_roots, Create, Document, DocumentError,
+    EntryKind, FolderPath,
+};
+#[cfg(feature = "settings")]
+use crate::{FileSystemEntity, Format, ParseError};
+
+/// A list of folders to look for a file in, from the most important to the least important.
+///
+/// Linux apps are expected to read the user's own configuration first, and fall back to system-wide defaults
+/// in `$XDG_CONFIG_DIRS` (`/etc/xdg` if not set). [`SearchPath::new`](SearchPath::new) builds this list from a [`Folder`](crate::Folder) or [`FolderPath`](FolderPath):
+/// the folder itself, then the same subfolders under each of the [`system_folders`](crate::Roots::system_folders) of the current [`Roots`](crate::Roots).
+///
+/// *Config*: the user's config folder, then the folders in `$XDG_CONFIG_DIRS`.
+///
+/// *Data* and *DataLocal*: the user's data folder, then the folders in `$XDG_DATA_DIRS` (`/usr/local/share` and `/usr/share` if not set).
+///
+/// Any other folder, and any folder on systems other than Linux: only the folder itself.
+///
+/// ```ignore
+/// let search_path = SearchPath::new(Project(Config([]).with_id("com", "example", "App")))?;
+/// // e.g. ~/.config/app/settings.toml, then /etc/xdg/app/settings.toml
+/// for document in search_path.find("settings.toml") {
+///     println!("{}", document.path());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SearchPath {
+    folders: Vec<PathBuf>,
+}
+
+impl SearchPath {
+    /// The search path of `folder`, starting with `folder` itself.
+    ///
+    /// Returns an error if `folder` could not be found.
+    pub fn new(folder: impl Into<FolderPath>) -> Result<Self, DocumentError> {
+        let folder = folder.into();
+        let mut folders = vec![folder.to_pathbuf_result("")?];
+        for mut system_folder in with_current_roots(|roots| roots.system_folders(folder.base()))? {
+            system_folder.extend(folder.subfolders());
+            folders.push(system_folder);
+        }
+        Ok(Self { folders })
+    }
+
+    /// A search path of the given folders, from the most important to the least important.
+    pub fn from_folders(folders: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self {
+            folders: folders.into_iter().collect(),
+        }
+    }
+
+    /// The folders of this search path, from the most important to the least important.
+    pub fn folders(&self) -> &[PathBuf] {
+        &self.folders
+    }
+
+    /// Every existing file named `filename` in the folders of this search path, from the most important to the least important,
+    /// as [`Document`](Document)s with `Create::No` whose alias is `filename`.
+    pub fn find(&self, filename: impl Display) -> Vec<Document> {
+        let filename = filename.to_string();
+        let backend = current_backend();
+        self.folders
+            .iter()
+            .map(|folder| folder.join(&filename))
+            .filter(|pathbuf| {
+                backend
+                    .metadata(pathbuf)
+                    .is_ok_and(|metadata| metadata.kind == EntryKind::File)
+            })
+            .filter_map(|pathbuf| {
+                Document::at_path(pathbuf.display(), filename.clone(), Create::No).ok()
+            })
+            .collect()
+    }
+
+    /// The most important existing file named `filename` in the folders of this search path.
+    pub fn find_first(&self, filename: impl Display) -> Option<Document> {
+        self.find(filename).into_iter().next()
+    }
+
+    /// Load every existing file named `filename` in the folders of this search path, in the [`Format`](crate::Format) given by its extension,
+    /// and deep-merge them into one `T`: keys in more important files override the same keys in less important files,
+    /// and nested maps are merged key by key. See [`Merged`](Merged) for which file each key came from.
+    ///
+    /// ```ignore
+    /// let settings = SearchPath::new(Project(Config([]).with_id("com", "example", "App")))?.load_merged::<Settings>("settings.toml")?;
+    /// println!("The theme is set in {:?}", settings.source("appearance.theme"));
+    /// ```
+    ///
+    /// Requires the `settings` feature. Returns an error if any of the files could not be read or parsed, or the merged value is not a valid `T`.
+    #[cfg(feature = "settings")]
+    pub fn load_merged<T: DeserializeOwned>(
+        &self,
+        filename: impl Display,
+    ) -> Result<Merged<T>, DocumentError> {
+        let filename = filename.to_string();
+        let path = match self.folders.first() {
+            Some(folder) => folder.join(&filename).display().to_string(),
+            None => filename.clone(),
+        };
+        let format = match Path::new(&filename).extension() {
+            Some(extension) => Format::from_extension(&extension.to_string_lossy()),
+            None => None,
+        };
+        let Some(format) = format else {
+            Err(DocumentError::UnknownFormat(path))?
+        };
+        let mut merged = Value::Object(Map::new());
+        let mut sources = BTreeMap::new();
+        for document in self.find(&filename).iter().rev() {
+            let layer: Value = format.deserialize(document.path(), &document.content()?)?;
+            merge(&mut merged, layer, "", &document.pathbuf, &mut sources);
+        }
+        match serde_json::from_value(merged) {
+            Ok(value) => Ok(Merged { value, sources }),
+            Err(error) => Err(DocumentError::CouldNotParseFile(
+                path,
+                ParseError::new(format, None, error),
+            )),
+        }
+    }
+}
+
+/// Merge `layer` into `merged`, recording `source` as the origin of every key `layer` sets.
+/// Maps are merged key by key, anything else replaces the value in `merged`.
+#[cfg(feature = "settings")]
+fn merge(
+    merged: &mut Value,
+    layer: Value,
+    prefix: &str,
+    source: &Path,
+    sources: &mut BTreeMap<String, PathBuf>,
+) {
+    match (merged, layer) {
+        (Value::Object(merged), Value::Object(layer)) => {
+            for (key, value) in layer {
+                let key_path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                match merged.get_mut(&key) {
+                    Some(existing) if existing.is_object() && value.is_object() => {
+                        merge(existing, value, &key_path, source, sources)
+                    }
+                    _ => {
+                        // The value is replaced as a whole, so nothing below it comes from other files any more.
+                        let nested_prefix = format!("{key_path}.");
+                        sources
+                            .retain(|key, _| *key != key_path && !key.starts_with(&nested_prefix));
+                        record_sources(&value, &key_path, source, sources);
+                        merged.insert(key, value);
+                    }
+                }
+            }
+        }
+        (merged, layer) => {
+            sources.clear();
+            record_sources(&layer, prefix, source, sources);
+            *merged = layer;
+        }
+    }
+}
+
+/// Record `source` as the origin of `value` at `key_path`, or of every key inside it if it is a non-empty map.
+#[cfg(feature = "settings")]
+fn record_sources(
+    value: &Value,
+    key_path: &str,
+    source: &Path,
+    sources: &mut BTreeMap<String, PathBuf>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let key_path = if key_path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{key_path}.{key}")
+                };
+                record_sources(value, &key_path, source, sources);
+            }
+        }
+        _ => {
+            sources.insert(key_path.to_string(), source.to_path_buf());
+        }
+    }
+}
+
+/// A value deep-merged from several files by [`SearchPath::load_merged`](SearchPath::load_merged), and which file each key came from.
+///
+/// Keys are written as their path through nested maps, separated by ".", e.g. "appearance.theme".
+/// Only keys whose values are not maps are recorded; lists are not merged and count as one key.
+///
+/// Merged derefs to `T`.
+#[cfg(feature = "settings")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged<T> {
+    value: T,
+    sources: BTreeMap<String, PathBuf>,
+}
+
+#[cfg(feature = "settings")]
+impl<T> Merged<T> {
+    /// The file the value at `key` came from, e.g. `merged.source("appearance.theme")`.
+    /// Returns None if no file sets this key, or its value is a map.
+    pub fn source(&self, key: &str) -> Option<&Path> {
+        self.sources.get(key).map(PathBuf::as_path)
+    }
+
+    /// Every key set by the files, and the file each of them came from.
+    pub fn sources(&self) -> &BTreeMap<String, PathBuf> {
+        &self.sources
+    }
+
+    /// Take the merged value out.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+#[cfg(feature = "settings")]
+impl<T> Deref for Merged<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
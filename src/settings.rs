@@ -0,0 +1,258 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{Debug, Display},
+    ops::{Deref, DerefMut},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{Create, Document, DocumentError, FileSystemEntity, FolderPath, Format, ParseError};
+
+/// The key under which [`Settings`](Settings) stores the schema version in the settings file.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+type Migration = Box<dyn Fn(Value) -> Result<Value, Box<dyn Error + Send + Sync>>>;
+
+/// The current schema version of a [`Settings`](Settings) file, and how to migrate older files to it.
+///
+/// *version*: the schema version written to the settings file. Files without a version are treated as version 0.
+///
+/// *migration*: a function which converts the content of a settings file from one version to the next.
+/// Versions without a migration are left as they are.
+///
+/// ```ignore
+/// // Version 1 renamed "colour" to "theme".
+/// let schema = Schema::new(1).migration(0, |mut value| {
+///     let colour = value["colour"].take();
+///     value["theme"] = colour;
+///     Ok(value)
+/// });
+/// ```
+#[derive(Default)]
+pub struct Schema {
+    version: u64,
+    migrations: BTreeMap<u64, Migration>,
+}
+
+impl Schema {
+    /// A schema at `version`, without migrations.
+    pub fn new(version: u64) -> Self {
+        Self {
+            version,
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Convert a settings file at `from_version` to `from_version + 1` with `migrate`.
+    /// The file content is given as a [`serde_json::Value`], whichever [`Format`](Format) the file is in.
+    ///
+    /// Errors returned from `migrate` must be `Send + Sync` (e.g. a `String` converted with `.into()`, or a [`DocumentError`](DocumentError)),
+    /// so that the [`DocumentError`](DocumentError) they end up in can be sent to other threads.
+    pub fn migration(
+        mut self,
+        from_version: u64,
+        migrate: impl Fn(Value) -> Result<Value, Box<dyn Error + Send + Sync>> + 'static,
+    ) -> Self {
+        self.migrations.insert(from_version, Box::new(migrate));
+        self
+    }
+}
+
+impl Debug for Schema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Schema")
+            .field("version", &self.version)
+            .field("migrations", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// A value of type `T` kept in a settings file, e.g. under `Project(Config([]))`, in any [`Format`](Format) given by the file extension.
+///
+/// If the file does not exist yet, it is created with `T::default()`. If it was saved with an older [`Schema`](Schema) version,
+/// a backup of it is kept next to it (e.g. "settings.toml.v1.bak") and the migrations of the schema are run before the value is read.
+/// The schema version is stored in the file under [`SCHEMA_VERSION_KEY`](SCHEMA_VERSION_KEY), so `T` must be a struct or a map.
+///
+/// Settings derefs to `T`. Call [`save`](Settings::save) to write changes back to the file.
+///
+/// ```ignore
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Preferences { theme: String, font_size: u32 }
+///
+/// let config = Project(Config([]).with_id("com", "example", "App"));
+/// let mut preferences = Settings::<Preferences>::load_with_schema(config, "preferences.toml", Schema::new(1))?;
+/// preferences.font_size += 1;
+/// preferences.save()?;
+/// ```
+///
+/// Requires the `settings` feature, as well as the feature of the file [`Format`](Format).
+#[derive(Debug)]
+pub struct Settings<T> {
+    document: Document,
+    format: Format,
+    version: u64,
+    value: T,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> Settings<T> {
+    /// Load the settings file `filename` in `location`, with schema version 0 and no migrations.
+    ///
+    /// Returns an error if the file could not be created, read or parsed, or was saved with a newer schema version.
+    pub fn load(
+        location: impl Into<FolderPath>,
+        filename: impl Display,
+    ) -> Result<Self, DocumentError> {
+        Self::load_with_schema(location, filename, Schema::default())
+    }
+
+    /// Load the settings file `filename` in `location`, migrating it to the version of `schema` if needed.
+    ///
+    /// Returns an error if the file could not be created, read, parsed or backed up, was saved with a newer schema version,
+    /// or a migration failed.
+    pub fn load_with_schema(
+        location: impl Into<FolderPath>,
+        filename: impl Display,
+        schema: Schema,
+    ) -> Result<Self, DocumentError> {
+        let document = Document::at(location, filename, Create::OnlyIfNotExists)?;
+        let format = document.format()?;
+        let content = document.content()?;
+        let mut settings = Self {
+            document,
+            format,
+            version: schema.version,
+            value: T::default(),
+        };
+        if content.trim().is_empty() {
+            settings.save()?;
+            return Ok(settings);
+        }
+        let path = settings.document.path();
+        let mut value: Value = format.deserialize(path.clone(), &content)?;
+        let file_version = match value.get_mut(SCHEMA_VERSION_KEY).map(Value::take) {
+            Some(version) => match version.as_u64() {
+                Some(version) => version,
+                None => Err(DocumentError::CouldNotParseFile(
+                    path.clone(),
+                    ParseError::new(format, None, "the schema version is not a number"),
+                ))?,
+            },
+            None => 0,
+        };
+        if file_version > schema.version {
+            Err(DocumentError::SchemaVersionTooNew(
+                path.clone(),
+                file_version,
+            ))?
+        }
+        let migrated = file_version < schema.version;
+        if migrated {
+            let backup_name = format!("{}.v{file_version}.bak", settings.document.name());
+            Document::at_path(
+                settings
+                    .document
+                    .pathbuf
+                    .with_file_name(backup_name)
+                    .display(),
+                "backup",
+                Create::OnlyIfNotExists,
+            )?
+            .replace_atomically(content.as_bytes())?;
+            for version in file_version..schema.version {
+                if let Some(migrate) = schema.migrations.get(&version) {
+                    value = match migrate(value) {
+                        Ok(value) => value,
+                        Err(error) => Err(DocumentError::CouldNotMigrateFile(
+                            path.clone(),
+                            version,
+                            error,
+                        ))?,
+                    };
+                }
+            }
+        }
+        if let Value::Object(map) = &mut value {
+            map.remove(SCHEMA_VERSION_KEY);
+        }
+        settings.value = match serde_json::from_value(value) {
+            Ok(value) => value,
+            Err(error) => Err(DocumentError::CouldNotParseFile(
+                path,
+                ParseError::new(format, None, error),
+            ))?,
+        };
+        if migrated {
+            settings.save()?;
+        }
+        Ok(settings)
+    }
+
+    /// Write the value of these Settings and their schema version to the settings file, atomically.
+    ///
+    /// Returns an error if the value is not a struct or a map, could not be converted to the file format, or the file could not be replaced.
+    pub fn save(&mut self) -> Result<&mut Self, DocumentError> {
+        let mut value = match serde_json::to_value(&self.value) {
+            Ok(value) => value,
+            Err(error) => Err(DocumentError::CouldNotSerializeValue(
+                self.document.path(),
+                error.to_string(),
+            ))?,
+        };
+        match &mut value {
+            Value::Object(map) => {
+                map.insert(SCHEMA_VERSION_KEY.to_string(), Value::from(self.version));
+            }
+            _ => Err(DocumentError::CouldNotSerializeValue(
+                self.document.path(),
+                "settings must be a struct or a map".to_string(),
+            ))?,
+        }
+        if self.format == Format::Toml {
+            remove_nulls(&mut value);
+        }
+        self.document.save_as(&value, self.format)?;
+        Ok(self)
+    }
+
+    /// The schema version of these Settings.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The settings file.
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// Take the value out of these Settings.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Remove the null values from the maps in `value`, recursively. TOML has no null, so a `None` is written by leaving out its key instead.
+fn remove_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(remove_nulls);
+        }
+        Value::Array(values) => values.iter_mut().for_each(remove_nulls),
+        _ => {}
+    }
+}
+
+impl<T> Deref for Settings<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Settings<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
@@ -0,0 +1,374 @@
+use std::{
+    fs::{
+        create_dir, create_dir_all, hard_link, metadata, read_dir, read_to_string, remove_dir,
+        remove_file, rename, symlink_metadata, DirBuilder, OpenOptions,
+    },
+    io::{self, Write},
+    os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    backend::{is_local_disk, Backend},
+    document::parse_filepath,
+    roots::with_current_roots,
+    DocumentError, FileSystemEntity,
+};
+
+/// A file or folder in the trash, following the [freedesktop.org Trash specification](https://specifications.freedesktop.org/trash-spec/latest/)
+/// used by Linux desktops.
+///
+/// Put files and folders in the trash with [`Document::trash`](crate::Document::trash) and [`Folder::trash`](crate::Folder::trash),
+/// list what is in the trash with [`TrashItem::list`](TrashItem::list), and put an item back with [`restore`](TrashItem::restore).
+///
+/// ```ignore
+/// for item in TrashItem::list()? {
+///     if item.original_path().ends_with("report.txt") {
+///         item.restore()?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrashItem {
+    /// The trash folder this item is in, which contains the "files" and "info" folders.
+    trash_folder: PathBuf,
+    /// The name of this item in the "files" folder of the trash.
+    name: String,
+    original_path: PathBuf,
+    deletion_date: String,
+}
+
+impl TrashItem {
+    /// Where this item was before it was put in the trash.
+    pub fn original_path(&self) -> &Path {
+        &self.original_path
+    }
+
+    /// When this item was put in the trash, in local time in the format "YYYY-MM-DDThh:mm:ss".
+    pub fn deletion_date(&self) -> &str {
+        &self.deletion_date
+    }
+
+    /// Where this item is now, inside the trash.
+    pub fn trashed_path(&self) -> PathBuf {
+        self.trash_folder.join("files").join(&self.name)
+    }
+
+    /// Every item in the user's trash and in the trash folders of other mounted disks.
+    ///
+    /// Returns an error if the user's trash folder cannot be found.
+    pub fn list() -> Result<Vec<TrashItem>, DocumentError> {
+        let mut trash_folders = vec![home_trash()?];
+        let uid = current_uid();
+        for mount_point in mount_points() {
+            trash_folders.push(mount_point.join(".Trash").join(uid.to_string()));
+            trash_folders.push(mount_point.join(format!(".Trash-{uid}")));
+        }
+        let mut items = Vec::new();
+        for trash_folder in trash_folders {
+            let Ok(entries) = read_dir(trash_folder.join("info")) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                let Some(name) = file_name.strip_suffix(".trashinfo") else {
+                    continue;
+                };
+                let Ok(trash_info) = read_to_string(entry.path()) else {
+                    continue;
+                };
+                if let Some(item) = parse_trash_info(&trash_folder, name, &trash_info) {
+                    items.push(item);
+                }
+            }
+        }
+        Ok(items)
+    }
+
+    /// Put this item back where it was before it was put in the trash.
+    ///
+    /// Never replaces anything: returns an error of kind [`AlreadyExists`](crate::DocumentErrorKind::AlreadyExists) if something else is at that location,
+    /// even if it was put there while restoring, or an error if the item could not be moved.
+    pub fn restore(&self) -> Result<(), DocumentError> {
+        let result = (|| {
+            if let Some(parent_folder) = self.original_path.parent() {
+                create_dir_all(parent_folder)?;
+            }
+            rename_without_replacing(&self.trashed_path(), &self.original_path)?;
+            remove_file(self.info_path())
+        })();
+        match result {
+            Ok(_) => Ok(()),
+            Err(error) => Err(DocumentError::CouldNotRestore(
+                self.original_path.path(),
+                error,
+            )),
+        }
+    }
+
+    fn info_path(&self) -> PathBuf {
+        self.trash_folder
+            .join("info")
+            .join(format!("{}.trashinfo", self.name))
+    }
+}
+
+/// Move the file or folder at `pathbuf` in `backend` into the trash. The trash is on the disk, so `backend` must be [`LocalDisk`](crate::LocalDisk).
+pub(crate) fn trash(backend: &dyn Backend, pathbuf: &Path) -> Result<TrashItem, DocumentError> {
+    let could_not_trash = |error| DocumentError::CouldNotTrash(pathbuf.to_path_buf().path(), error);
+    if !is_local_disk(backend) {
+        Err(could_not_trash(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the trash is only available for files on the disk",
+        )))?
+    }
+    let pathbuf = absolute_pathbuf(pathbuf).map_err(could_not_trash)?;
+    let (trash_folder, top_folder) = trash_folder_for(&pathbuf).map_err(could_not_trash)?;
+    let original_path = match &top_folder {
+        Some(top_folder) => pathbuf
+            .strip_prefix(top_folder)
+            .unwrap_or(&pathbuf)
+            .to_path_buf(),
+        None => pathbuf.clone(),
+    };
+    let deletion_date = local_time_now();
+    let trash_info = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode(&original_path.to_string_lossy()),
+        deletion_date
+    );
+    let (name, duplicate_number, extension) = parse_filepath(pathbuf.clone());
+    let extension = extension.unwrap_or_default();
+    let mut number = duplicate_number.unwrap_or(0);
+    let mut trash_name = pathbuf.name();
+    loop {
+        let files_path = trash_folder.join("files").join(&trash_name);
+        let info_path = trash_folder
+            .join("info")
+            .join(format!("{trash_name}.trashinfo"));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&info_path)
+        {
+            Ok(_) if symlink_metadata(&files_path).is_ok() => {
+                let _ = remove_file(&info_path);
+            }
+            Ok(mut info_file) => {
+                let result = info_file
+                    .write_all(trash_info.as_bytes())
+                    .and_then(|_| info_file.sync_all())
+                    .and_then(|_| rename(&pathbuf, &files_path));
+                if let Err(error) = result {
+                    let _ = remove_file(&info_path);
+                    Err(could_not_trash(error))?
+                }
+                return Ok(TrashItem {
+                    trash_folder,
+                    name: trash_name,
+                    original_path: match top_folder {
+                        Some(top_folder) => top_folder.join(original_path),
+                        None => original_path,
+                    },
+                    deletion_date,
+                });
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(error) => Err(could_not_trash(error))?,
+        }
+        number += 1;
+        trash_name = format!("{name}({number}){extension}");
+    }
+}
+
+/// Move the file or folder at `from` to `to`, failing with [`AlreadyExists`](io::ErrorKind::AlreadyExists) if something is at `to`,
+/// even if it appears there while moving.
+///
+/// A file is linked at `to` before it is removed at `from`, which both fail rather than replace.
+/// A folder is renamed over an empty folder created at `to` first, which fails if anything is put in it meanwhile.
+fn rename_without_replacing(from: &Path, to: &Path) -> io::Result<()> {
+    if symlink_metadata(from)?.is_dir() {
+        create_dir(to)?;
+        rename(from, to).inspect_err(|_| {
+            let _ = remove_dir(to);
+        })
+    } else {
+        hard_link(from, to)?;
+        remove_file(from).inspect_err(|_| {
+            let _ = remove_file(to);
+        })
+    }
+}
+
+/// The absolute path of the file or folder at `pathbuf`, without following it if it is a symbolic link:
+/// only its parent folder is canonicalized, so that the link itself is trashed rather than the file it points to.
+fn absolute_pathbuf(pathbuf: &Path) -> io::Result<PathBuf> {
+    let Some(name) = pathbuf.file_name() else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the path does not end with a file or folder name",
+        ))?
+    };
+    let parent_folder = match pathbuf.parent() {
+        Some(parent_folder) if parent_folder != Path::new("") => parent_folder,
+        _ => Path::new("."),
+    };
+    let pathbuf = parent_folder.canonicalize()?.join(name);
+    symlink_metadata(&pathbuf)?;
+    Ok(pathbuf)
+}
+
+/// The user's own trash folder, from the current [`Roots`](crate::Roots).
+fn home_trash() -> Result<PathBuf, DocumentError> {
+    with_current_roots(|roots| roots.trash_folder())
+}
+
+/// The trash folder to use for `pathbuf`, created if needed, and the top folder of its disk if it is not the user's own trash folder.
+fn trash_folder_for(pathbuf: &Path) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    let home_trash =
+        home_trash().map_err(|error| io::Error::new(io::ErrorKind::NotFound, error.to_string()))?;
+    let device = symlink_metadata(pathbuf)?.dev();
+    let home_trash_device = home_trash
+        .ancestors()
+        .find_map(|folder| metadata(folder).ok())
+        .map(|metadata| metadata.dev());
+    if home_trash_device == Some(device) {
+        create_trash_folder(&home_trash)?;
+        return Ok((home_trash, None));
+    }
+    let top_folder = pathbuf
+        .ancestors()
+        .skip(1)
+        .take_while(|folder| metadata(folder).is_ok_and(|metadata| metadata.dev() == device))
+        .last()
+        .unwrap_or(Path::new("/"))
+        .to_path_buf();
+    let uid = current_uid();
+    let shared_trash = top_folder.join(".Trash");
+    let shared_trash_is_valid = symlink_metadata(&shared_trash)
+        .is_ok_and(|metadata| metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0);
+    if shared_trash_is_valid {
+        let trash_folder = shared_trash.join(uid.to_string());
+        if create_trash_folder(&trash_folder).is_ok() {
+            return Ok((trash_folder, Some(top_folder)));
+        }
+    }
+    let trash_folder = top_folder.join(format!(".Trash-{uid}"));
+    create_trash_folder(&trash_folder)?;
+    Ok((trash_folder, Some(top_folder)))
+}
+
+fn create_trash_folder(trash_folder: &Path) -> io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true).mode(0o700);
+    builder.create(trash_folder.join("files"))?;
+    builder.create(trash_folder.join("info"))
+}
+
+fn parse_trash_info(trash_folder: &Path, name: &str, trash_info: &str) -> Option<TrashItem> {
+    let mut lines = trash_info.lines().map(str::trim);
+    lines.find(|line| *line == "[Trash Info]")?;
+    let mut path = None;
+    let mut deletion_date = String::new();
+    for line in lines {
+        if line.starts_with('[') {
+            break;
+        } else if let Some(value) = line.strip_prefix("Path=") {
+            path = Some(PathBuf::from(percent_decode(value)));
+        } else if let Some(value) = line.strip_prefix("DeletionDate=") {
+            deletion_date = value.to_string();
+        }
+    }
+    let path = path?;
+    let original_path = if path.is_absolute() {
+        path
+    } else {
+        // Trash folders on other disks store paths relative to the top folder of the disk,
+        // which contains either ".Trash-$uid" or ".Trash/$uid".
+        let parent_folder = trash_folder.parent()?;
+        let top_folder = match parent_folder.file_name() {
+            Some(name) if name == ".Trash" => parent_folder.parent()?,
+            _ => parent_folder,
+        };
+        top_folder.join(path)
+    };
+    Some(TrashItem {
+        trash_folder: trash_folder.to_path_buf(),
+        name: name.to_string(),
+        original_path,
+        deletion_date,
+    })
+}
+
+fn mount_points() -> Vec<PathBuf> {
+    let Ok(mounts) = read_to_string("/proc/self/mounts") else {
+        return Vec::new();
+    };
+    mounts
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(|mount_point| PathBuf::from(mount_point.replace("\\040", " ")))
+        .collect()
+}
+
+fn current_uid() -> u32 {
+    // SAFETY: getuid cannot fail and has no side effects.
+    unsafe { libc::getuid() }
+}
+
+fn local_time_now() -> String {
+    // SAFETY: time and localtime_r only write to the provided, properly sized variables.
+    let time = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut time: libc::tm = std::mem::zeroed();
+        libc::localtime_r(&now, &mut time);
+        time
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        time.tm_year + 1900,
+        time.tm_mon + 1,
+        time.tm_mday,
+        time.tm_hour,
+        time.tm_min,
+        time.tm_sec
+    )
+}
+
+fn percent_encode(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(encoded: &str) -> String {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok());
+        match (
+            bytes[index],
+            hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+        ) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,203 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{backend::BackendRef, Create, Document, DocumentError, EntryKind, FileSystemEntity};
+
+/// The order in which [`Walk`](Walk) visits the files and subfolders of each folder.
+///
+/// *Unsorted*: whatever order the operating system lists them in. Fastest, but may differ between runs and devices.
+///
+/// *Name*: alphabetical order of their names.
+///
+/// *Modified*: least recently modified first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SortOrder {
+    #[default]
+    Unsorted,
+    Name,
+    Modified,
+}
+
+/// An iterator over the files in a folder and its subfolders, provided by [`FolderPath::walk`](crate::FolderPath::walk) or [`Folder::walk`](crate::Folder::walk).
+///
+/// Each file is provided as a [`Document`](Document) with `Create::No`, whose [`alias`](Document::alias) is its path relative to the folder walked,
+/// e.g. "2024/March/invoice.pdf". A Walk can be given to [`with`](crate::with) as-is.
+///
+/// Configure it before iterating:
+///
+/// *max_depth*: how many levels of folders to look into. 1 means only the files directly in the folder, and 0 means no files at all. Unlimited by default.
+///
+/// *include_hidden*: whether to include files and folders whose names start with ".". Off by default.
+///
+/// *follow_symlinks*: whether to look into symbolic links which point to folders. Off by default.
+/// Symbolic links which point to files are always included.
+///
+/// *sort_by*: the [`SortOrder`](SortOrder) of the files and subfolders in each folder. Unsorted by default.
+///
+/// ```ignore
+/// with(
+///     User(Downloads(["Invoices"])).walk().max_depth(2).sort_by(SortOrder::Name),
+///     |d| {
+///         for (alias, document) in d {
+///             println!("{alias}: {} bytes", document.bytes()?.len());
+///         }
+///         Ok(())
+///     },
+/// );
+/// ```
+#[derive(Debug)]
+pub struct Walk {
+    root: Result<PathBuf, Option<DocumentError>>,
+    max_depth: Option<usize>,
+    include_hidden: bool,
+    follow_symlinks: bool,
+    sort_order: SortOrder,
+    /// The entries of each folder being walked which are not visited yet, in reverse order, with the depth of the folder.
+    pending: Vec<(usize, Vec<PathBuf>)>,
+    visited_folders: HashSet<FolderId>,
+    started: bool,
+    backend: BackendRef,
+}
+
+/// What identifies a folder already walked, so that symbolic links which loop back are only walked once.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum FolderId {
+    Inode(u64, u64),
+    Path(PathBuf),
+}
+
+impl Walk {
+    pub(crate) fn new(root: Result<PathBuf, DocumentError>) -> Self {
+        Self {
+            root: root.map_err(Some),
+            max_depth: None,
+            include_hidden: false,
+            follow_symlinks: false,
+            sort_order: SortOrder::default(),
+            pending: Vec::new(),
+            visited_folders: HashSet::new(),
+            started: false,
+            backend: BackendRef::default(),
+        }
+    }
+
+    /// How many levels of folders to look into. 1 means only the files directly in the folder, and 0 means that the Walk provides no files at all.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Whether to include files and folders whose names start with ".".
+    pub fn include_hidden(mut self, include_hidden: bool) -> Self {
+        self.include_hidden = include_hidden;
+        self
+    }
+
+    /// Whether to look into symbolic links which point to folders.
+    pub fn follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// The order in which to visit the files and subfolders of each folder.
+    pub fn sort_by(mut self, sort_order: SortOrder) -> Self {
+        self.sort_order = sort_order;
+        self
+    }
+
+    /// Read the entries of `folder` into `pending`, skipping hidden ones if needed.
+    fn enter(&mut self, folder: &Path, depth: usize) -> Result<(), DocumentError> {
+        let backend = self.backend.0.as_ref();
+        let folder_id = match backend.metadata(folder) {
+            Ok(metadata) => match (metadata.device, metadata.inode) {
+                (Some(device), Some(inode)) => Some(FolderId::Inode(device, inode)),
+                _ => folder.canonicalize().ok().map(FolderId::Path),
+            },
+            Err(_) => None,
+        };
+        if let Some(folder_id) = folder_id {
+            if !self.visited_folders.insert(folder_id) {
+                return Ok(());
+            }
+        }
+        let mut pathbufs = match backend.read_dir(folder) {
+            Ok(pathbufs) => pathbufs,
+            Err(error) => Err(DocumentError::CouldNotReadFolder(
+                folder.to_path_buf().path(),
+                error,
+            ))?,
+        };
+        if !self.include_hidden {
+            pathbufs.retain(|pathbuf| !pathbuf.name().starts_with('.'));
+        }
+        match self.sort_order {
+            SortOrder::Unsorted => {}
+            SortOrder::Name => pathbufs.sort(),
+            SortOrder::Modified => pathbufs.sort_by_key(|pathbuf| {
+                backend
+                    .metadata(pathbuf)
+                    .ok()
+                    .and_then(|metadata| metadata.modified)
+                    .unwrap_or(SystemTime::UNIX_EPOCH)
+            }),
+        }
+        pathbufs.reverse();
+        self.pending.push((depth, pathbufs));
+        Ok(())
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<Document, DocumentError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let root = match &mut self.root {
+            Ok(root) => root.clone(),
+            Err(error) => return error.take().map(Err),
+        };
+        if !self.started {
+            self.started = true;
+            if self.max_depth == Some(0) {
+                return None;
+            }
+            if let Err(error) = self.enter(&root, 1) {
+                return Some(Err(error));
+            }
+        }
+        while let Some((depth, pathbufs)) = self.pending.last_mut() {
+            let depth = *depth;
+            let Some(pathbuf) = pathbufs.pop() else {
+                self.pending.pop();
+                continue;
+            };
+            let Ok(metadata) = self.backend.0.metadata(&pathbuf) else {
+                continue;
+            };
+            if metadata.kind == EntryKind::Folder {
+                let within_depth = self.max_depth.is_none_or(|max_depth| depth < max_depth);
+                if within_depth && (self.follow_symlinks || !metadata.is_symlink) {
+                    if let Err(error) = self.enter(&pathbuf, depth + 1) {
+                        return Some(Err(error));
+                    }
+                }
+            } else if metadata.kind == EntryKind::File {
+                let alias = pathbuf
+                    .strip_prefix(&root)
+                    .unwrap_or(&pathbuf)
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                return Some(Document::with_backend(
+                    self.backend.clone(),
+                    pathbuf,
+                    alias,
+                    Create::No,
+                ));
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,56 @@
+use std::{error::Error, fmt::Display};
+
+use crate::DocumentError;
+
+/// The error returned by [`try_with`](crate::try_with).
+///
+/// *Setup*: some of the [`Document`](crate::Document)s could not be created, so the closure was not run.
+/// Contains every failed Document as its position in the `documents` parameter (starting from 0), and why it failed.
+///
+/// *Closure*: the closure was run, and it returned an error.
+///
+/// *InvalidOptions*: the [`WithOptions`](crate::WithOptions) cannot be used together, so nothing was done. Contains the reason.
+///
+/// *Commit*: the closure succeeded, but its staged writes (see [`WithOptions::stage_writes`](crate::WithOptions::stage_writes)) could not be committed.
+/// The staged writes which were not committed yet are discarded; the ones committed before the failure are kept, as the commit is not atomic across Documents.
+///
+/// Note: files and folders created for the Documents by their [`Create`](crate::Create) policies are removed again whichever error is returned,
+/// except after a *Commit* error which happened once some staged writes were already committed, as those may have been written into them.
+#[derive(Debug)]
+pub enum WithError {
+    Setup(Vec<(usize, DocumentError)>),
+    Closure(Box<dyn Error>),
+    InvalidOptions(String),
+    Commit(DocumentError),
+}
+
+impl Display for WithError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Setup(errors) => {
+                write!(f, "Could not set up {} document(s):", errors.len())?;
+                for (index, error) in errors {
+                    write!(f, "\n  #{index}: {error}")?;
+                }
+                Ok(())
+            }
+            Self::Closure(error) => write!(f, "{error}"),
+            Self::InvalidOptions(reason) => write!(f, "Invalid options: {reason}"),
+            Self::Commit(error) => write!(f, "Could not commit staged writes: {error}"),
+        }
+    }
+}
+
+impl Error for WithError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Setup(errors) => match errors.as_slice() {
+                [(_, error)] => Some(error),
+                _ => None,
+            },
+            Self::Closure(error) => Some(error.as_ref()),
+            Self::InvalidOptions(_) => None,
+            Self::Commit(error) => Some(error),
+        }
+    }
+}
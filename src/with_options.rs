@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use crate::{Backend, LockKind, Roots, WithError};
+
+/// Options which change how [`try_with_options`](crate::try_with_options) sets up and runs its closure.
+///
+/// Start with `WithOptions::new()` (or `WithOptions::default()`) and chain the options you need.
+///
+/// ```ignore
+/// try_with_options(
+///     || [Document::at(User(Documents([])), "report.txt", Create::No)],
+///     WithOptions::new().stage_writes(true),
+///     |mut d| {
+///         d["report.txt"].append(b"Q3 figures")?;
+///         d["report.txt"].append(b"Q4 figures")?;
+///         Ok(())
+///     },
+/// )?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WithOptions {
+    pub(crate) stage_writes: bool,
+    pub(crate) roots: Option<Arc<dyn Roots>>,
+    pub(crate) backend: Option<Arc<dyn Backend>>,
+    pub(crate) lock: Option<LockKind>,
+}
+
+impl WithOptions {
+    /// The default options: writes go straight to the files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to stage all writes made in the closure and only commit them if the closure succeeds.
+    ///
+    /// When enabled, each [`Document`](crate::Document) reads and writes a temporary copy of its file while the closure runs.
+    /// If the closure succeeds, each copy is renamed over its file. If the closure fails, the copies are removed and the files are left untouched.
+    /// Each rename is atomic, but they are not atomic together: if one fails, the files renamed before it keep their new content. See [`WithError::Commit`](crate::WithError::Commit).
+    ///
+    /// While writes are staged, the Documents cannot be moved, renamed, deleted or moved to the trash, as their files only change when the writes are committed.
+    /// [`copy_to`](crate::Document::copy_to) copies the staged content.
+    ///
+    /// Cannot be used together with [`lock`](WithOptions::lock), because the renamed copies would not be locked.
+    pub fn stage_writes(mut self, stage_writes: bool) -> Self {
+        self.stage_writes = stage_writes;
+        self
+    }
+
+    /// The [`Roots`](crate::Roots) to resolve folders with while [`try_with_options`](crate::try_with_options) runs, e.g. a [`Sandbox`](crate::Sandbox).
+    ///
+    /// Documents are resolved when they are created, so these Roots are used by the Documents created by the `documents` function
+    /// and in the closure, but not by Documents created before `try_with_options` is called.
+    ///
+    /// ```ignore
+    /// try_with_options(
+    ///     || [
+    ///         Document::at(User(Documents([])), "notes.txt", Create::OnlyIfNotExists),
+    ///         Document::at(User(Documents([])), "todo.txt", Create::OnlyIfNotExists),
+    ///     ],
+    ///     WithOptions::new().roots(Sandbox::new()?),
+    ///     |mut d| {
+    ///         d["notes.txt"].append(b"Only in the sandbox")?;
+    ///         Ok(())
+    ///     },
+    /// )?;
+    /// ```
+    pub fn roots(mut self, roots: impl Roots + 'static) -> Self {
+        self.roots = Some(Arc::new(roots));
+        self
+    }
+
+    /// The [`Backend`](crate::Backend) to store files in while [`try_with_options`](crate::try_with_options) runs, e.g. [`InMemory`](crate::InMemory).
+    ///
+    /// Like [`roots`](WithOptions::roots), this Backend is used by the Documents created by the `documents` function and in the closure.
+    /// Documents created before `try_with_options` is called keep the Backend which was current when they were created.
+    ///
+    /// ```ignore
+    /// let memory = InMemory::new();
+    /// try_with_options(
+    ///     || [Document::at(User(Documents([])), "notes.txt", Create::OnlyIfNotExists)],
+    ///     WithOptions::new().backend(memory.clone()),
+    ///     |mut d| {
+    ///         d["notes.txt"].append(b"Never on disk")?;
+    ///         Ok(())
+    ///     },
+    /// )?;
+    /// ```
+    pub fn backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    /// Take an advisory lock of `kind` on the file of every [`Document`](crate::Document) before running the closure,
+    /// and hold the locks until its writes are committed or undone. See [`DocumentLock`](crate::DocumentLock).
+    ///
+    /// The files are locked in the order of the aliases of their Documents, so that two `try_with_options` which lock the same files
+    /// never wait for each other forever. A file which is given twice is only locked once.
+    /// If a lock cannot be taken, [`WithError::Setup`](crate::WithError::Setup) is returned and the closure is not run.
+    ///
+    /// Cannot be used together with [`stage_writes`](WithOptions::stage_writes), which commits by putting new files in place of the locked ones:
+    /// [`WithError::InvalidOptions`](crate::WithError::InvalidOptions) is returned instead.
+    ///
+    /// ```ignore
+    /// try_with_options(
+    ///     || [Document::at(Project(Data([]).with_id("com", "example", "App")), "log.txt", Create::OnlyIfNotExists)],
+    ///     WithOptions::new().lock(LockKind::Exclusive),
+    ///     |mut d| {
+    ///         d["log.txt"].append(b"Not interleaved with other processes\n")?;
+    ///         Ok(())
+    ///     },
+    /// )?;
+    /// ```
+    pub fn lock(mut self, kind: LockKind) -> Self {
+        self.lock = Some(kind);
+        self
+    }
+
+    /// Returns an error if these options cannot be used together.
+    pub(crate) fn check(&self) -> Result<(), WithError> {
+        if self.stage_writes && self.lock.is_some() {
+            Err(WithError::InvalidOptions(
+                "staged writes replace the locked files, so they cannot be locked".to_string(),
+            ))?
+        }
+        Ok(())
+    }
+}